@@ -0,0 +1,138 @@
+use std::ffi::CStr;
+
+/// Error type returned by all fallible functions in this crate.
+///
+/// Failures reported by HMMER/Easel are mapped from their Easel status code
+/// (eslENOTFOUND, eslEFORMAT, ...) and carry the contents of the relevant
+/// `errbuf`, which may be empty when the C code did not fill it in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HmmerError {
+    /// A file or key was not found (eslENOTFOUND).
+    NotFound(String),
+    /// Input was empty or misformatted (eslEFORMAT).
+    Format(String),
+    /// An invalid argument or parameter was given (eslEINVAL).
+    InvalidArgument(String),
+    /// Parameters were incompatible, e.g. mismatched alphabets (eslEINCOMPAT).
+    Incompatible(String),
+    /// A memory allocation failed (eslEMEM).
+    OutOfMemory,
+    /// End of file was reached where more data was expected (eslEOF).
+    UnexpectedEof(String),
+    /// A write failed (eslEWRITE).
+    Write(String),
+    /// Any other non-OK Easel status code.
+    Easel { status: i32, message: String },
+    /// Alignment/trace computation failed.
+    AlignmentFailure,
+    /// Stockholm serialization failed.
+    SerializationFailure,
+    /// No sequences were provided.
+    NoSequences,
+    /// Opening or parsing an HMM file failed. `source` is the underlying
+    /// error, carrying the message from the HMM file's errbuf.
+    HmmFile {
+        /// Path of the HMM file.
+        path: String,
+        /// 0-based index of the model being read, or None if the file could
+        /// not be opened.
+        model_index: Option<usize>,
+        /// Line at which the parse error was detected (ASCII files only).
+        line: Option<usize>,
+        source: Box<HmmerError>,
+    },
+}
+
+impl HmmerError {
+    /// Map a (non-OK) Easel status code and message to an error.
+    pub(crate) fn from_status(status: i32, message: impl Into<String>) -> HmmerError {
+        let message = message.into();
+        match status as u32 {
+            libhmmer_sys::eslENOTFOUND => HmmerError::NotFound(message),
+            libhmmer_sys::eslEFORMAT => HmmerError::Format(message),
+            libhmmer_sys::eslEINVAL => HmmerError::InvalidArgument(message),
+            libhmmer_sys::eslEINCOMPAT => HmmerError::Incompatible(message),
+            libhmmer_sys::eslEMEM => HmmerError::OutOfMemory,
+            libhmmer_sys::eslEOF => HmmerError::UnexpectedEof(message),
+            libhmmer_sys::eslEWRITE => HmmerError::Write(message),
+            _ => HmmerError::Easel { status, message },
+        }
+    }
+
+    /// Map a (non-OK) Easel status code to an error, taking the message from
+    /// a NUL-terminated C `errbuf`.
+    ///
+    /// # Safety
+    /// `errbuf` must be NULL or point to a NUL-terminated string.
+    pub(crate) unsafe fn from_errbuf(status: i32, errbuf: *const libc::c_char) -> HmmerError {
+        let message = if errbuf.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(errbuf).to_string_lossy().into_owned()
+        };
+        HmmerError::from_status(status, message)
+    }
+
+    /// The Easel status code corresponding to this error, if any.
+    pub fn status(&self) -> Option<i32> {
+        let status = match self {
+            HmmerError::NotFound(_) => libhmmer_sys::eslENOTFOUND,
+            HmmerError::Format(_) => libhmmer_sys::eslEFORMAT,
+            HmmerError::InvalidArgument(_) => libhmmer_sys::eslEINVAL,
+            HmmerError::Incompatible(_) => libhmmer_sys::eslEINCOMPAT,
+            HmmerError::OutOfMemory => libhmmer_sys::eslEMEM,
+            HmmerError::UnexpectedEof(_) => libhmmer_sys::eslEOF,
+            HmmerError::Write(_) => libhmmer_sys::eslEWRITE,
+            HmmerError::Easel { status, .. } => return Some(*status),
+            HmmerError::HmmFile { source, .. } => return source.status(),
+            HmmerError::AlignmentFailure
+            | HmmerError::SerializationFailure
+            | HmmerError::NoSequences => return None,
+        };
+        Some(status as i32)
+    }
+}
+
+impl std::fmt::Display for HmmerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HmmerError::NotFound(msg) => write!(f, "Not found: {msg}"),
+            HmmerError::Format(msg) => write!(f, "Format error: {msg}"),
+            HmmerError::InvalidArgument(msg) => write!(f, "Invalid argument: {msg}"),
+            HmmerError::Incompatible(msg) => write!(f, "Incompatible parameters: {msg}"),
+            HmmerError::OutOfMemory => write!(f, "Memory allocation failed"),
+            HmmerError::UnexpectedEof(msg) => write!(f, "Unexpected end of file: {msg}"),
+            HmmerError::Write(msg) => write!(f, "Write failed: {msg}"),
+            HmmerError::Easel { status, message } => {
+                write!(f, "Easel error (status {status}): {message}")
+            }
+            HmmerError::AlignmentFailure => write!(f, "Alignment/trace computation failed"),
+            HmmerError::SerializationFailure => write!(f, "Stockholm serialization failed"),
+            HmmerError::NoSequences => write!(f, "No sequences provided"),
+            HmmerError::HmmFile {
+                path,
+                model_index,
+                line,
+                source,
+            } => {
+                write!(f, "Error reading HMM file {path}")?;
+                if let Some(i) = model_index {
+                    write!(f, ", model {i}")?;
+                }
+                if let Some(l) = line {
+                    write!(f, ", line {l}")?;
+                }
+                write!(f, ": {source}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for HmmerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HmmerError::HmmFile { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
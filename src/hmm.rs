@@ -2,62 +2,43 @@ use log::*;
 use std::ffi::CStr;
 use std::ffi::CString;
 
+use crate::libhmmer_sys_extras::*;
+use crate::{Alphabet, HmmerError};
+
 pub struct Hmm {
     pub c_hmm: *mut libhmmer_sys::P7_HMM,
 }
 
 impl Hmm {
-    pub fn read_hmms_from_path(path: &std::path::Path) -> Result<Vec<Hmm>, &'static str> {
-        // char          errbuf[eslERRBUFSIZE];
-        #[allow(unused_mut)]
-        let mut errbuf = CString::new(vec![1; libhmmer_sys::eslERRBUFSIZE as usize])
-            .unwrap()
-            .into_raw();
-
-        let hmmfile = CString::new(path.to_string_lossy().as_bytes())
-            .unwrap()
-            .into_raw();
+    /// Read all HMMs from a file into memory. For large databases, consider
+    /// [`HmmReader`], which yields models one at a time.
+    pub fn read_hmms_from_path(path: &std::path::Path) -> Result<Vec<Hmm>, HmmerError> {
+        HmmReader::from_path(path)?.collect()
+    }
 
-        // Open file
-        let mut hfp: *mut libhmmer_sys::P7_HMMFILE = std::ptr::null_mut();
-        let status1 = unsafe {
-            libhmmer_sys::p7_hmmfile_OpenE(hmmfile, std::ptr::null_mut(), &mut hfp, errbuf)
-        };
-        // eslOK = 0
-        if status1 != 0 {
-            error!("Error in initial reading of HMM file");
-            return Err("Error in initial reading of HMM file");
-        }
-        debug!("HMM file opened successfully");
+    /// Read all HMMs from an in-memory buffer holding one or more HMMER ASCII
+    /// models, e.g. one embedded with `include_bytes!`.
+    pub fn read_hmms_from_bytes(bytes: &[u8]) -> Result<Vec<Hmm>, HmmerError> {
+        HmmReader::from_bytes(bytes.to_vec())?.collect()
+    }
 
-        // Read each HMM
-        let mut hmms = Vec::new();
-        loop {
-            // ESL_ALPHABET *abc     = NULL;	/* alphabet (set from the HMM file)*/
-            // Set to NULL to not force alphabet
-            let mut abc: *mut libhmmer_sys::ESL_ALPHABET = std::ptr::null_mut();
-            // P7_HMM       *hmm     = NULL;
-            let mut hmm: *mut libhmmer_sys::P7_HMM = std::ptr::null_mut();
-
-            let status2 = unsafe { libhmmer_sys::p7_hmmfile_Read(hfp, &mut abc, &mut hmm) };
-            if status2 == libhmmer_sys::eslEOF as i32 {
-                debug!("EOF reached");
-                break;
-            } else if status2 != 0 {
-                error!("Error in reading HMM from opened file");
-                return Err("Error in reading HMM from opened file");
-            }
-            debug!("HMM read successfully");
-            hmms.push(Hmm { c_hmm: hmm });
-        }
+    /// Read all HMMs from a string holding one or more HMMER ASCII models.
+    pub fn read_hmms_from_str(text: &str) -> Result<Vec<Hmm>, HmmerError> {
+        Hmm::read_hmms_from_bytes(text.as_bytes())
+    }
 
-        // retake pointer to free memory
-        unsafe {
-            let _ = CString::from_raw(errbuf);
-            let _ = CString::from_raw(hmmfile);
-        }
+    /// Read all HMMs in HMMER ASCII format from any [`std::io::Read`] source.
+    /// The source is read to the end before parsing.
+    pub fn read_hmms_from_reader<R: std::io::Read>(reader: R) -> Result<Vec<Hmm>, HmmerError> {
+        HmmReader::from_reader(reader)?.collect()
+    }
 
-        Ok(hmms)
+    /// Read the HMM with the given name or accession from a file, as
+    /// `hmmfetch` does. If the file has an SSI index (see
+    /// [`HmmReader::create_ssi_index`]) only that model is parsed, otherwise
+    /// the file is read until the model is found.
+    pub fn fetch_from_path(path: &std::path::Path, key: &str) -> Result<Hmm, HmmerError> {
+        HmmReader::from_path(path)?.fetch(key)
     }
 
     pub fn c_alphabet(&self) -> *const libhmmer_sys::ESL_ALPHABET {
@@ -93,12 +74,835 @@ impl Hmm {
             unsafe { CStr::from_ptr(my_desc).to_string_lossy().to_string() }
         }
     }
+
+    /// The alphabet of the model (ALPH), or None if it is not protein, DNA
+    /// or RNA.
+    pub fn alphabet(&self) -> Option<Alphabet> {
+        unsafe { Alphabet::from_c_type((*self.c_alphabet()).type_) }
+    }
+
+    /// Number of sequences the model was trained on (NSEQ).
+    pub fn nseq(&self) -> Option<u32> {
+        let nseq = unsafe { (*self.c_hmm).nseq };
+        u32::try_from(nseq).ok()
+    }
+
+    /// Effective number of sequences after weighting (EFFN).
+    pub fn eff_nseq(&self) -> Option<f32> {
+        let eff_nseq = unsafe { (*self.c_hmm).eff_nseq };
+        (eff_nseq >= 0.0).then_some(eff_nseq)
+    }
+
+    /// Checksum of the training alignment (CKSUM).
+    pub fn checksum(&self) -> Option<u32> {
+        self.has_flag(p7H_CHKSUM)
+            .then(|| unsafe { (*self.c_hmm).checksum })
+    }
+
+    /// Date the model was built (DATE).
+    pub fn creation_time(&self) -> Option<String> {
+        unsafe { optional_string((*self.c_hmm).ctime) }
+    }
+
+    /// Command line(s) used to build the model, one per line (COM).
+    pub fn command_log(&self) -> Option<String> {
+        unsafe { optional_string((*self.c_hmm).comlog) }
+    }
+
+    /// Upper bound on the length of sequences the model generates, such
+    /// that all but 1e-7 of the probability mass is shorter (MAXL).
+    pub fn max_length(&self) -> Option<u32> {
+        let max_length = unsafe { (*self.c_hmm).max_length };
+        u32::try_from(max_length).ok().filter(|l| *l > 0)
+    }
+
+    /// Pfam gathering thresholds (GA).
+    pub fn gathering_cutoffs(&self) -> Option<ScoreCutoffs> {
+        self.cutoffs(p7H_GA, p7_GA1, p7_GA2)
+    }
+
+    /// Pfam trusted cutoffs (TC).
+    pub fn trusted_cutoffs(&self) -> Option<ScoreCutoffs> {
+        self.cutoffs(p7H_TC, p7_TC1, p7_TC2)
+    }
+
+    /// Pfam noise cutoffs (NC).
+    pub fn noise_cutoffs(&self) -> Option<ScoreCutoffs> {
+        self.cutoffs(p7H_NC, p7_NC1, p7_NC2)
+    }
+
+    fn cutoffs(&self, flag: i32, seq_index: usize, dom_index: usize) -> Option<ScoreCutoffs> {
+        let cutoff = unsafe { (*self.c_hmm).cutoff };
+        (self.has_flag(flag) && cutoff[seq_index] != p7_CUTOFF_UNSET).then_some(ScoreCutoffs {
+            sequence: cutoff[seq_index],
+            domain: cutoff[dom_index],
+        })
+    }
+
+    /// E-value calibration parameters of the MSV, Viterbi and Forward
+    /// filters (STATS LOCAL lines), or None if the model is not calibrated.
+    pub fn evalue_parameters(&self) -> Option<EvalueParameters> {
+        let evparam = unsafe { (*self.c_hmm).evparam };
+        self.has_flag(p7H_STATS).then_some(EvalueParameters {
+            msv_mu: evparam[p7_MMU],
+            msv_lambda: evparam[p7_MLAMBDA],
+            viterbi_mu: evparam[p7_VMU],
+            viterbi_lambda: evparam[p7_VLAMBDA],
+            forward_tau: evparam[p7_FTAU],
+            forward_lambda: evparam[p7_FLAMBDA],
+        })
+    }
+
+    /// Copy the descriptive fields of the model header into a plain Rust
+    /// value, which stays valid after the model is dropped.
+    pub fn metadata(&self) -> HmmMetadata {
+        HmmMetadata {
+            name: self.name(),
+            accession: unsafe { optional_string((*self.c_hmm).acc) },
+            description: unsafe { optional_string((*self.c_hmm).desc) },
+            length: self.length(),
+            alphabet: self.alphabet(),
+            nseq: self.nseq(),
+            eff_nseq: self.eff_nseq(),
+            checksum: self.checksum(),
+            max_length: self.max_length(),
+            gathering_cutoffs: self.gathering_cutoffs(),
+            trusted_cutoffs: self.trusted_cutoffs(),
+            noise_cutoffs: self.noise_cutoffs(),
+            evalue_parameters: self.evalue_parameters(),
+        }
+    }
+
+    /// Reference annotation line from the training alignment, one character
+    /// per match state (RF).
+    pub fn reference_annotation(&self) -> Option<String> {
+        unsafe { self.annotation_line(p7H_RF, (*self.c_hmm).rf) }
+    }
+
+    /// Model mask annotation line, one character per match state (MM).
+    pub fn model_mask(&self) -> Option<String> {
+        unsafe { self.annotation_line(p7H_MMASK, (*self.c_hmm).mm) }
+    }
+
+    /// Consensus residue line, one character per match state (CONS).
+    /// Upper case residues are highly conserved.
+    pub fn consensus(&self) -> Option<String> {
+        unsafe { self.annotation_line(p7H_CONS, (*self.c_hmm).consensus) }
+    }
+
+    /// Consensus structure annotation line, one character per match state
+    /// (CS).
+    pub fn consensus_structure(&self) -> Option<String> {
+        unsafe { self.annotation_line(p7H_CS, (*self.c_hmm).cs) }
+    }
+
+    /// Map of each match state onto its (1-based) column of the training
+    /// alignment (MAP).
+    pub fn alignment_map(&self) -> Option<Vec<i32>> {
+        let map = unsafe { (*self.c_hmm).map };
+        if !self.has_flag(p7H_MAP) || map.is_null() {
+            return None;
+        }
+        // map[0] is unused; the states are 1..M
+        let m = self.length() as usize;
+        Some(unsafe { std::slice::from_raw_parts(map.add(1), m) }.to_vec())
+    }
+
+    /// Model-specific residue composition (COMPO), averaged over the
+    /// expected occupancy of the match and insert states.
+    pub fn composition(&self) -> Option<Vec<f32>> {
+        let k = unsafe { (*self.c_alphabet()).K as usize };
+        let compo = unsafe { (*self.c_hmm).compo };
+        self.has_flag(p7H_COMPO).then(|| compo[..k].to_vec())
+    }
+
+    /// Set the name of the model. Names are written as a single token in HMM
+    /// files, so must not be empty or contain whitespace.
+    pub fn set_name(&mut self, name: &str) -> Result<(), HmmerError> {
+        let name_cstr = annotation_cstring("Name", name, true)?;
+        let status =
+            unsafe { libhmmer_sys::p7_hmm_SetName(self.c_hmm, name_cstr.as_ptr() as *mut _) };
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_status(status, "p7_hmm_SetName failed"));
+        }
+        Ok(())
+    }
+
+    /// Set the accession of the model, or remove it with None. Accessions
+    /// must not be empty or contain whitespace.
+    pub fn set_accession(&mut self, acc: Option<&str>) -> Result<(), HmmerError> {
+        let acc_cstr = acc
+            .map(|acc| annotation_cstring("Accession", acc, true))
+            .transpose()?;
+        let status = unsafe {
+            libhmmer_sys::p7_hmm_SetAccession(
+                self.c_hmm,
+                acc_cstr
+                    .as_ref()
+                    .map_or(std::ptr::null_mut(), |a| a.as_ptr() as *mut _),
+            )
+        };
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_status(
+                status,
+                "p7_hmm_SetAccession failed",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Set the one-line description of the model, or remove it with None.
+    pub fn set_description(&mut self, desc: Option<&str>) -> Result<(), HmmerError> {
+        let desc_cstr = desc
+            .map(|desc| annotation_cstring("Description", desc, false))
+            .transpose()?;
+        let status = unsafe {
+            libhmmer_sys::p7_hmm_SetDescription(
+                self.c_hmm,
+                desc_cstr
+                    .as_ref()
+                    .map_or(std::ptr::null_mut(), |d| d.as_ptr() as *mut _),
+            )
+        };
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_status(
+                status,
+                "p7_hmm_SetDescription failed",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Set the Pfam gathering thresholds (GA), or remove them with None.
+    pub fn set_gathering_cutoffs(&mut self, cutoffs: Option<ScoreCutoffs>) {
+        self.set_cutoffs(cutoffs, p7H_GA, p7_GA1, p7_GA2)
+    }
+
+    /// Set the Pfam trusted cutoffs (TC), or remove them with None.
+    pub fn set_trusted_cutoffs(&mut self, cutoffs: Option<ScoreCutoffs>) {
+        self.set_cutoffs(cutoffs, p7H_TC, p7_TC1, p7_TC2)
+    }
+
+    /// Set the Pfam noise cutoffs (NC), or remove them with None.
+    pub fn set_noise_cutoffs(&mut self, cutoffs: Option<ScoreCutoffs>) {
+        self.set_cutoffs(cutoffs, p7H_NC, p7_NC1, p7_NC2)
+    }
+
+    fn set_cutoffs(
+        &mut self,
+        cutoffs: Option<ScoreCutoffs>,
+        flag: i32,
+        seq_index: usize,
+        dom_index: usize,
+    ) {
+        unsafe {
+            let hmm = &mut *self.c_hmm;
+            match cutoffs {
+                Some(cutoffs) => {
+                    hmm.cutoff[seq_index] = cutoffs.sequence;
+                    hmm.cutoff[dom_index] = cutoffs.domain;
+                    hmm.flags |= flag;
+                }
+                None => {
+                    hmm.cutoff[seq_index] = p7_CUTOFF_UNSET;
+                    hmm.cutoff[dom_index] = p7_CUTOFF_UNSET;
+                    hmm.flags &= !flag;
+                }
+            }
+        }
+    }
+
+    /// Recalculate the model's residue composition (COMPO) from its current
+    /// emission and transition probabilities, as hmmbuild does. Call this
+    /// after changing the probabilities directly.
+    pub fn update_composition(&mut self) -> Result<(), HmmerError> {
+        let status = unsafe { libhmmer_sys::p7_hmm_SetComposition(self.c_hmm) };
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_status(
+                status,
+                "p7_hmm_SetComposition failed",
+            ));
+        }
+        Ok(())
+    }
+
+    fn has_flag(&self, flag: i32) -> bool {
+        unsafe { (*self.c_hmm).flags & flag != 0 }
+    }
+
+    /// Read an annotation line, which is stored as a string with a leading
+    /// space for the unused position 0.
+    unsafe fn annotation_line(&self, flag: i32, line: *const libc::c_char) -> Option<String> {
+        if !self.has_flag(flag) || line.is_null() {
+            return None;
+        }
+        let bytes = CStr::from_ptr(line).to_bytes();
+        Some(String::from_utf8_lossy(bytes.get(1..).unwrap_or_default()).into_owned())
+    }
+
+    /// Write the HMM in HMMER ASCII format, as `hmmconvert -a` does. Call
+    /// repeatedly on the same writer to build a multi-model file.
+    pub fn write_ascii<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        format: HmmFileFormat,
+    ) -> Result<(), HmmerError> {
+        let bytes = unsafe {
+            write_to_memstream(|fp| match format {
+                HmmFileFormat::V2 => libhmmer_sys::p7_h2io_WriteASCII(fp, self.c_hmm),
+                _ => libhmmer_sys::p7_hmmfile_WriteASCII(fp, format.code(), self.c_hmm),
+            })?
+        };
+        writer
+            .write_all(&bytes)
+            .map_err(|e| HmmerError::Write(e.to_string()))
+    }
+
+    /// Write the HMM in HMMER3 binary format, as `hmmconvert -b` does. The
+    /// HMMER2 format has no binary form, so [`HmmFileFormat::V2`] is an
+    /// error.
+    pub fn write_binary<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+        format: HmmFileFormat,
+    ) -> Result<(), HmmerError> {
+        if format == HmmFileFormat::V2 {
+            return Err(HmmerError::InvalidArgument(
+                "HMMER2 format cannot be written in binary".to_string(),
+            ));
+        }
+        let bytes = unsafe {
+            write_to_memstream(|fp| {
+                libhmmer_sys::p7_hmmfile_WriteBinary(fp, format.code(), self.c_hmm)
+            })?
+        };
+        writer
+            .write_all(&bytes)
+            .map_err(|e| HmmerError::Write(e.to_string()))
+    }
+
+    /// Write the HMM to a new file in HMMER ASCII format, replacing any
+    /// existing file.
+    pub fn write_ascii_to_path(
+        &self,
+        path: &std::path::Path,
+        format: HmmFileFormat,
+    ) -> Result<(), HmmerError> {
+        let mut file = create_file(path)?;
+        self.write_ascii(&mut file, format)
+    }
+
+    /// Write the HMM to a new file in HMMER3 binary format, replacing any
+    /// existing file.
+    pub fn write_binary_to_path(
+        &self,
+        path: &std::path::Path,
+        format: HmmFileFormat,
+    ) -> Result<(), HmmerError> {
+        let mut file = create_file(path)?;
+        self.write_binary(&mut file, format)
+    }
+
+    /// The HMM in HMMER ASCII format, as it would be written to a file.
+    pub fn to_ascii_string(&self, format: HmmFileFormat) -> Result<String, HmmerError> {
+        let mut bytes = Vec::new();
+        self.write_ascii(&mut bytes, format)?;
+        String::from_utf8(bytes).map_err(|_| HmmerError::SerializationFailure)
+    }
+}
+
+/// A pair of Pfam bit score cutoffs (GA, TC or NC), applied to whole
+/// sequences and to individual domains.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScoreCutoffs {
+    pub sequence: f32,
+    pub domain: f32,
+}
+
+/// E-value calibration parameters of a model, as written on its STATS
+/// lines. The MSV and Viterbi filter scores follow Gumbel distributions with
+/// location mu; the Forward score follows an exponential tail with location
+/// tau.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EvalueParameters {
+    pub msv_mu: f32,
+    pub msv_lambda: f32,
+    pub viterbi_mu: f32,
+    pub viterbi_lambda: f32,
+    pub forward_tau: f32,
+    pub forward_lambda: f32,
+}
+
+/// An owned copy of the header of a model, made with [`Hmm::metadata`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HmmMetadata {
+    pub name: String,
+    pub accession: Option<String>,
+    pub description: Option<String>,
+    pub length: u32,
+    pub alphabet: Option<Alphabet>,
+    pub nseq: Option<u32>,
+    pub eff_nseq: Option<f32>,
+    pub checksum: Option<u32>,
+    pub max_length: Option<u32>,
+    pub gathering_cutoffs: Option<ScoreCutoffs>,
+    pub trusted_cutoffs: Option<ScoreCutoffs>,
+    pub noise_cutoffs: Option<ScoreCutoffs>,
+    pub evalue_parameters: Option<EvalueParameters>,
+}
+
+/// Convert a name, accession or description for storing on a P7_HMM. Values
+/// that would not survive being written to an HMM file are rejected.
+fn annotation_cstring(what: &str, value: &str, single_token: bool) -> Result<CString, HmmerError> {
+    if single_token && (value.is_empty() || value.contains(char::is_whitespace)) {
+        return Err(HmmerError::InvalidArgument(format!(
+            "{what} must not be empty or contain whitespace"
+        )));
+    }
+    if value.contains(['\n', '\r']) {
+        return Err(HmmerError::InvalidArgument(format!(
+            "{what} must not contain line breaks"
+        )));
+    }
+    CString::new(value)
+        .map_err(|_| HmmerError::InvalidArgument(format!("{what} must not contain null bytes")))
+}
+
+/// A C string that may be NULL or empty, either of which means it is unset.
+pub(crate) unsafe fn optional_string(s: *const libc::c_char) -> Option<String> {
+    if s.is_null() || *s == 0 {
+        None
+    } else {
+        Some(CStr::from_ptr(s).to_string_lossy().into_owned())
+    }
+}
+
+/// HMM save file format versions, as offered by `hmmconvert`. `V3f` is the
+/// current format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HmmFileFormat {
+    /// HMMER2 ASCII format (`hmmconvert -2`). Some information, such as the
+    /// filter calibrations, is lost.
+    V2,
+    V3a,
+    V3b,
+    V3c,
+    V3d,
+    V3e,
+    #[default]
+    V3f,
+}
+
+impl HmmFileFormat {
+    fn code(self) -> i32 {
+        match self {
+            HmmFileFormat::V2 => p7_HMMFILE_20,
+            HmmFileFormat::V3a => p7_HMMFILE_3a,
+            HmmFileFormat::V3b => p7_HMMFILE_3b,
+            HmmFileFormat::V3c => p7_HMMFILE_3c,
+            HmmFileFormat::V3d => p7_HMMFILE_3d,
+            HmmFileFormat::V3e => p7_HMMFILE_3e,
+            HmmFileFormat::V3f => p7_HMMFILE_3f,
+        }
+    }
+}
+
+fn create_file(path: &std::path::Path) -> Result<std::fs::File, HmmerError> {
+    std::fs::File::create(path)
+        .map_err(|e| HmmerError::Write(format!("Failed to create {}: {}", path.display(), e)))
+}
+
+/// Run a HMMER writer function against an in-memory stream, returning the
+/// bytes it wrote.
+unsafe fn write_to_memstream<F>(write: F) -> Result<Vec<u8>, HmmerError>
+where
+    F: FnOnce(*mut libhmmer_sys::FILE) -> i32,
+{
+    let mut buf: *mut libc::c_char = std::ptr::null_mut();
+    let mut buf_size: libc::size_t = 0;
+    let fp = libc::open_memstream(&mut buf, &mut buf_size);
+    if fp.is_null() {
+        return Err(HmmerError::OutOfMemory);
+    }
+
+    let status = write(fp as *mut libhmmer_sys::FILE);
+    libc::fclose(fp);
+
+    let result = if status != libhmmer_sys::eslOK as i32 {
+        Err(HmmerError::from_status(status, "Failed to write HMM"))
+    } else if buf.is_null() {
+        Ok(Vec::new())
+    } else {
+        Ok(std::slice::from_raw_parts(buf as *const u8, buf_size).to_vec())
+    };
+
+    if !buf.is_null() {
+        libc::free(buf as *mut libc::c_void);
+    }
+
+    result
+}
+
+/// Streaming reader over the models in an HMM file. Each call to `next()`
+/// parses a single model, so memory use does not grow with the size of the
+/// database. The file is closed when the reader is dropped.
+///
+/// Iteration stops after the first error.
+pub struct HmmReader {
+    hfp: *mut libhmmer_sys::P7_HMMFILE,
+    // Backing memory for readers created with from_bytes(). The HMM file's
+    // parser points into it, so it must live as long as hfp.
+    _buffer: Option<Vec<u8>>,
+    nread: usize,
+    finished: bool,
+}
+
+impl HmmReader {
+    /// Open an HMM file for reading.
+    pub fn from_path(path: &std::path::Path) -> Result<HmmReader, HmmerError> {
+        // char          errbuf[eslERRBUFSIZE];
+        let mut errbuf = vec![0 as libc::c_char; libhmmer_sys::eslERRBUFSIZE as usize];
+
+        let hmmfile = CString::new(path.to_string_lossy().as_bytes()).map_err(|_| {
+            HmmerError::InvalidArgument("HMM file path must not contain null bytes".to_string())
+        })?;
+
+        // Open file
+        let mut hfp: *mut libhmmer_sys::P7_HMMFILE = std::ptr::null_mut();
+        let status = unsafe {
+            libhmmer_sys::p7_hmmfile_OpenE(
+                hmmfile.as_ptr(),
+                std::ptr::null_mut(),
+                &mut hfp,
+                errbuf.as_mut_ptr(),
+            )
+        };
+        if status != libhmmer_sys::eslOK as i32 {
+            error!("Error in initial reading of HMM file");
+            return Err(HmmerError::HmmFile {
+                path: path.to_string_lossy().into_owned(),
+                model_index: None,
+                line: None,
+                source: Box::new(unsafe { HmmerError::from_errbuf(status, errbuf.as_ptr()) }),
+            });
+        }
+        debug!("HMM file opened successfully");
+
+        Ok(HmmReader {
+            hfp,
+            _buffer: None,
+            nread: 0,
+            finished: false,
+        })
+    }
+
+    /// Read HMMs from an in-memory buffer holding one or more HMMER ASCII
+    /// models. Binary (pressed) models are not supported.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<HmmReader, HmmerError> {
+        let size = i32::try_from(bytes.len()).map_err(|_| {
+            HmmerError::InvalidArgument("HMM buffer is too large (> 2GB)".to_string())
+        })?;
+
+        let mut hfp: *mut libhmmer_sys::P7_HMMFILE = std::ptr::null_mut();
+        let status = unsafe {
+            libhmmer_sys::p7_hmmfile_OpenBuffer(
+                bytes.as_ptr() as *const libc::c_char,
+                size,
+                &mut hfp,
+            )
+        };
+        if status != libhmmer_sys::eslOK as i32 {
+            error!("Error in initial reading of HMM buffer");
+            return Err(HmmerError::HmmFile {
+                path: BUFFER_NAME.to_string(),
+                model_index: None,
+                line: None,
+                source: Box::new(HmmerError::from_status(
+                    status,
+                    "Buffer does not start with a HMMER ASCII format tag",
+                )),
+            });
+        }
+        // p7_hmmfile_OpenBuffer() leaves hfp->f NULL but does not set
+        // do_stdin, so reading a second model would call ftello(NULL) to
+        // record its offset. Marking the handle as a stream skips that, and
+        // is otherwise only consulted when closing hfp->f, which is NULL.
+        unsafe {
+            (*hfp).do_stdin = 1; // TRUE
+        }
+        debug!("HMM buffer opened successfully");
+
+        Ok(HmmReader {
+            hfp,
+            _buffer: Some(bytes),
+            nread: 0,
+            finished: false,
+        })
+    }
+
+    /// Read HMMs in HMMER ASCII format from any [`std::io::Read`] source. The
+    /// source is read to the end before the first model is parsed.
+    pub fn from_reader<R: std::io::Read>(mut reader: R) -> Result<HmmReader, HmmerError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|e| HmmerError::HmmFile {
+                path: BUFFER_NAME.to_string(),
+                model_index: None,
+                line: None,
+                source: Box::new(HmmerError::from_status(
+                    libhmmer_sys::eslESYS as i32,
+                    e.to_string(),
+                )),
+            })?;
+        HmmReader::from_bytes(bytes)
+    }
+}
+
+impl HmmReader {
+    /// Create an SSI index for an HMM file, as `hmmfetch --index` does, so
+    /// that models can be fetched by name or accession without reading the
+    /// whole file. The index is written to `<path>.ssi`, replacing any
+    /// existing index. Returns the number of HMMs indexed.
+    ///
+    /// Pressed databases already have an index (`.h3i`), so do not need this.
+    pub fn create_ssi_index(path: &std::path::Path) -> Result<usize, HmmerError> {
+        let mut reader = HmmReader::from_path(path)?;
+        let hfp = reader.hfp;
+        let (fname, ssifile) = unsafe {
+            if (*hfp).do_gzip != 0 || (*hfp).do_stdin != 0 {
+                return Err(HmmerError::InvalidArgument(format!(
+                    "HMM file {} must be a normal file, not gzipped or a stdin pipe",
+                    path.display()
+                )));
+            }
+            let fname = CStr::from_ptr((*hfp).fname).to_owned();
+            // esl_sprintf(&ssifile, "%s.ssi", hfp->fname)
+            let mut ssifile = fname.clone().into_bytes();
+            ssifile.extend_from_slice(b".ssi");
+            let ssifile = CString::new(ssifile).map_err(|_| {
+                HmmerError::InvalidArgument("HMM file path must not contain null bytes".to_string())
+            })?;
+            (fname, ssifile)
+        };
+
+        // status = esl_newssi_Open(ssifile, FALSE, &ns);
+        // hmmfetch refuses to overwrite an index. Here an existing index is
+        // replaced (allow_overwrite = TRUE), so that it can be rebuilt after
+        // the HMM file changes.
+        let mut ns: *mut libhmmer_sys::ESL_NEWSSI = std::ptr::null_mut();
+        let status = unsafe { libhmmer_sys::esl_newssi_Open(ssifile.as_ptr(), 1, &mut ns) };
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_status(
+                status,
+                format!("Failed to create SSI index {}", ssifile.to_string_lossy()),
+            ));
+        }
+
+        let result = unsafe { reader.add_ssi_keys(ns, &fname) };
+        unsafe { libhmmer_sys::esl_newssi_Close(ns) };
+        let nhmm = result?;
+        debug!("Indexed {nhmm} HMMs into {}", ssifile.to_string_lossy());
+        Ok(nhmm)
+    }
+
+    /// Add every remaining model in the file to a new SSI index and write
+    /// it. Mirrors create_ssi_index() in hmmfetch.c.
+    unsafe fn add_ssi_keys(
+        &mut self,
+        ns: *mut libhmmer_sys::ESL_NEWSSI,
+        fname: &CStr,
+    ) -> Result<usize, HmmerError> {
+        // esl_newssi_AddFile(ns, hfp->fname, 0, &fh) /* 0 = format code (HMMs don't have any yet) */
+        let mut fh: u16 = 0;
+        let status = libhmmer_sys::esl_newssi_AddFile(ns, fname.as_ptr(), 0, &mut fh);
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_errbuf(status, (*ns).errbuf.as_ptr()));
+        }
+
+        let mut nhmm = 0;
+        for hmm in self.by_ref() {
+            // Keep the HMM alive until its name and accession have been
+            // copied into the index.
+            let hmm = hmm?;
+            let c_hmm = hmm.c_hmm;
+            nhmm += 1;
+            // if (hmm->name == NULL)           p7_Fail("Every HMM must have a name to be indexed. Failed to find name of HMM #%d\n", nhmm);
+            if (*c_hmm).name.is_null() {
+                return Err(HmmerError::InvalidArgument(format!(
+                    "Every HMM must have a name to be indexed. Failed to find name of HMM #{nhmm}"
+                )));
+            }
+            // esl_newssi_AddKey(ns, hmm->name, fh, hmm->offset, 0, 0)
+            let status =
+                libhmmer_sys::esl_newssi_AddKey(ns, (*c_hmm).name, fh, (*c_hmm).offset, 0, 0);
+            if status != libhmmer_sys::eslOK as i32 {
+                return Err(HmmerError::from_errbuf(status, (*ns).errbuf.as_ptr()));
+            }
+            // if (hmm->acc) esl_newssi_AddAlias(ns, hmm->acc, hmm->name)
+            if !(*c_hmm).acc.is_null() {
+                let status = libhmmer_sys::esl_newssi_AddAlias(ns, (*c_hmm).acc, (*c_hmm).name);
+                if status != libhmmer_sys::eslOK as i32 {
+                    return Err(HmmerError::from_errbuf(status, (*ns).errbuf.as_ptr()));
+                }
+            }
+        }
+
+        // esl_newssi_Write(ns)
+        let status = libhmmer_sys::esl_newssi_Write(ns);
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_errbuf(status, (*ns).errbuf.as_ptr()));
+        }
+        Ok(nhmm)
+    }
+
+    /// Whether the file was opened with an SSI index, so that
+    /// [`HmmReader::fetch`] can seek directly to a model.
+    pub fn has_ssi_index(&self) -> bool {
+        unsafe { !(*self.hfp).ssi.is_null() }
+    }
+
+    /// Read the HMM with the given name or accession. Mirrors onefetch() in
+    /// hmmfetch.c: with an SSI index the reader is first positioned at the
+    /// model using p7_hmmfile_PositionByKey(); without one, models are read
+    /// from the current position until a match is found.
+    ///
+    /// Iteration continues from the model after the fetched one.
+    pub fn fetch(&mut self, key: &str) -> Result<Hmm, HmmerError> {
+        let key_cstr = CString::new(key).map_err(|_| {
+            HmmerError::InvalidArgument("HMM key must not contain null bytes".to_string())
+        })?;
+        let fname = unsafe {
+            if (*self.hfp).fname.is_null() {
+                BUFFER_NAME.to_string()
+            } else {
+                CStr::from_ptr((*self.hfp).fname)
+                    .to_string_lossy()
+                    .into_owned()
+            }
+        };
+
+        if self.has_ssi_index() {
+            // status = p7_hmmfile_PositionByKey(hfp, key);
+            // if      (status == eslENOTFOUND) p7_Fail("HMM %s not found in SSI index for file %s\n", key, hfp->fname);
+            // else if (status == eslEFORMAT)   p7_Fail("Failed to parse SSI index for %s\n", hfp->fname);
+            // else if (status != eslOK)        p7_Fail("Failed to look up location of HMM %s in SSI index of file %s\n", key, hfp->fname);
+            let status =
+                unsafe { libhmmer_sys::p7_hmmfile_PositionByKey(self.hfp, key_cstr.as_ptr()) };
+            if status == libhmmer_sys::eslENOTFOUND as i32 {
+                return Err(HmmerError::NotFound(format!(
+                    "HMM {key} not found in SSI index for file {fname}"
+                )));
+            } else if status == libhmmer_sys::eslEFORMAT as i32 {
+                return Err(HmmerError::Format(format!(
+                    "Failed to parse SSI index for {fname}"
+                )));
+            } else if status != libhmmer_sys::eslOK as i32 {
+                return Err(HmmerError::from_status(
+                    status,
+                    format!("Failed to look up location of HMM {key} in SSI index of file {fname}"),
+                ));
+            }
+            // The file may have been repositioned before the end of a
+            // previous iteration.
+            self.finished = false;
+        }
+
+        // if (strcmp(key, hmm->name) == 0 || (hmm->acc && strcmp(key, hmm->acc) == 0)) break;
+        for hmm in self.by_ref() {
+            let hmm = hmm?;
+            if hmm.name() == key || hmm.acc() == key {
+                return Ok(hmm);
+            }
+        }
+        Err(HmmerError::NotFound(format!(
+            "HMM {key} not found in file {fname}"
+        )))
+    }
+}
+
+/// Name reported in errors for HMMs read from memory rather than a file.
+const BUFFER_NAME: &str = "<buffer>";
+
+impl Iterator for HmmReader {
+    type Item = Result<Hmm, HmmerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        // ESL_ALPHABET *abc     = NULL;	/* alphabet (set from the HMM file)*/
+        // Set to NULL to not force alphabet
+        let mut abc: *mut libhmmer_sys::ESL_ALPHABET = std::ptr::null_mut();
+        // P7_HMM       *hmm     = NULL;
+        let mut hmm: *mut libhmmer_sys::P7_HMM = std::ptr::null_mut();
+
+        let status = unsafe { libhmmer_sys::p7_hmmfile_Read(self.hfp, &mut abc, &mut hmm) };
+        if status == libhmmer_sys::eslEOF as i32 {
+            debug!("EOF reached");
+            self.finished = true;
+            return None;
+        } else if status != libhmmer_sys::eslOK as i32 {
+            error!("Error in reading HMM from opened file");
+            self.finished = true;
+            return Some(Err(unsafe {
+                hmmfile_read_error(self.hfp, status, self.nread)
+            }));
+        }
+        debug!("HMM read successfully");
+        self.nread += 1;
+        Some(Ok(Hmm { c_hmm: hmm }))
+    }
+}
+
+impl Drop for HmmReader {
+    fn drop(&mut self) {
+        unsafe {
+            libhmmer_sys::p7_hmmfile_Close(self.hfp);
+        }
+    }
+}
+
+/// Build the error for a failed p7_hmmfile_Read(), pointing at the model
+/// and (for ASCII files) line at which parsing failed.
+///
+/// # Safety
+/// `hfp` must be a valid, open HMM file.
+unsafe fn hmmfile_read_error(
+    hfp: *mut libhmmer_sys::P7_HMMFILE,
+    status: i32,
+    model_index: usize,
+) -> HmmerError {
+    let line = if (*hfp).efp.is_null() {
+        None
+    } else {
+        Some((*(*hfp).efp).linenumber as usize)
+    };
+    let path = if (*hfp).fname.is_null() {
+        BUFFER_NAME.to_string()
+    } else {
+        CStr::from_ptr((*hfp).fname).to_string_lossy().into_owned()
+    };
+    HmmerError::HmmFile {
+        path,
+        model_index: Some(model_index),
+        line,
+        source: Box::new(HmmerError::from_errbuf(status, (*hfp).errbuf.as_ptr())),
+    }
 }
 
 impl Drop for Hmm {
     fn drop(&mut self) {
+        // Every HMM is read or built with an alphabet of its own, which
+        // p7_hmm_Destroy() does not free.
         unsafe {
+            let abc = (*self.c_hmm).abc;
             libhmmer_sys::p7_hmm_Destroy(self.c_hmm);
+            libhmmer_sys::esl_alphabet_Destroy(abc.cast_mut());
         }
     }
 }
@@ -0,0 +1,192 @@
+use crate::Hmm;
+
+// enum p7h_transitions_e {
+//   p7H_MM = 0,
+//   p7H_MI = 1,
+//   p7H_MD = 2,
+//   p7H_IM = 3,
+//   p7H_II = 4,
+//   p7H_DM = 5,
+//   p7H_DD = 6
+// };
+// #define p7H_NTRANSITIONS 7
+/// The state transitions of a node of a Plan7 HMM, in the order HMMER stores
+/// (and writes) them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transition {
+    MatchToMatch,
+    MatchToInsert,
+    MatchToDelete,
+    InsertToMatch,
+    InsertToInsert,
+    DeleteToMatch,
+    DeleteToDelete,
+}
+
+impl Transition {
+    /// All transitions, in HMMER's order.
+    pub const ALL: [Transition; 7] = [
+        Transition::MatchToMatch,
+        Transition::MatchToInsert,
+        Transition::MatchToDelete,
+        Transition::InsertToMatch,
+        Transition::InsertToInsert,
+        Transition::DeleteToMatch,
+        Transition::DeleteToDelete,
+    ];
+
+    /// Index of the transition in a node's transition vector.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Label of the transition as written in HMM files, e.g. "m->i".
+    pub fn label(self) -> &'static str {
+        match self {
+            Transition::MatchToMatch => "m->m",
+            Transition::MatchToInsert => "m->i",
+            Transition::MatchToDelete => "m->d",
+            Transition::InsertToMatch => "i->m",
+            Transition::InsertToInsert => "i->i",
+            Transition::DeleteToMatch => "d->m",
+            Transition::DeleteToDelete => "d->d",
+        }
+    }
+}
+
+impl Hmm {
+    /// The residue symbols labelling the emission vectors of each node, in
+    /// order (e.g. "ACDEFGHIKLMNPQRSTVWY" for protein).
+    pub fn emission_symbols(&self) -> Vec<char> {
+        unsafe {
+            let abc = self.c_alphabet();
+            (0..(*abc).K as usize)
+                .map(|x| *(*abc).sym.add(x) as u8 as char)
+                .collect()
+        }
+    }
+
+    /// The null model residue frequencies that log-odds scores are
+    /// calculated against, as set by p7_bg_Create(): the standard amino
+    /// acid composition for protein, and uniform otherwise.
+    pub fn background_frequencies(&self) -> Vec<f32> {
+        unsafe {
+            let abc = self.c_alphabet();
+            let k = (*abc).K as usize;
+            let mut f = vec![0.0; k];
+            if (*abc).type_ == libhmmer_sys::eslAMINO as i32 {
+                libhmmer_sys::p7_AminoFrequencies(f.as_mut_ptr());
+            } else {
+                f.fill(1.0 / k as f32);
+            }
+            f
+        }
+    }
+
+    /// Node `k` of the model, for k in 0..=M. Node 0 holds the transitions
+    /// out of the begin state and the emissions of insert state 0; its match
+    /// emissions are unused.
+    pub fn node(&self, k: usize) -> Option<HmmNode<'_>> {
+        (k <= self.length() as usize).then_some(HmmNode { hmm: self, k })
+    }
+
+    /// The match nodes of the model, 1..=M.
+    pub fn nodes(&self) -> impl Iterator<Item = HmmNode<'_>> {
+        (1..=self.length() as usize).map(move |k| HmmNode { hmm: self, k })
+    }
+}
+
+/// A view of the parameters of one node of an HMM. Emission vectors are
+/// indexed in the order of [`Hmm::emission_symbols`], and transition vectors
+/// in the order of [`Transition::ALL`].
+///
+/// Probabilities are those stored in the model. Scores are log-odds in bits:
+/// emissions against [`Hmm::background_frequencies`], and transitions as
+/// log2 of their probability.
+#[derive(Clone, Copy)]
+pub struct HmmNode<'a> {
+    hmm: &'a Hmm,
+    k: usize,
+}
+
+impl<'a> HmmNode<'a> {
+    /// Index of the node in the model (0..=M).
+    pub fn index(&self) -> usize {
+        self.k
+    }
+
+    /// Match state emission probabilities (mat[k]).
+    pub fn match_emissions(&self) -> &'a [f32] {
+        unsafe { self.row((*self.hmm.c_hmm).mat, self.alphabet_size()) }
+    }
+
+    /// Insert state emission probabilities (ins[k]).
+    pub fn insert_emissions(&self) -> &'a [f32] {
+        unsafe { self.row((*self.hmm.c_hmm).ins, self.alphabet_size()) }
+    }
+
+    /// Transition probabilities out of the node (t[k]). At node M, only
+    /// transitions to the end state (M->M, I->M and D->M) are used.
+    pub fn transitions(&self) -> &'a [f32] {
+        unsafe { self.row((*self.hmm.c_hmm).t, Transition::ALL.len()) }
+    }
+
+    /// Match emission probability of a residue symbol, or None if the
+    /// symbol is not one of [`Hmm::emission_symbols`].
+    pub fn match_emission(&self, symbol: char) -> Option<f32> {
+        self.symbol_index(symbol).map(|x| self.match_emissions()[x])
+    }
+
+    /// Insert emission probability of a residue symbol, or None if the
+    /// symbol is not one of [`Hmm::emission_symbols`].
+    pub fn insert_emission(&self, symbol: char) -> Option<f32> {
+        self.symbol_index(symbol)
+            .map(|x| self.insert_emissions()[x])
+    }
+
+    /// Probability of a transition out of the node.
+    pub fn transition(&self, transition: Transition) -> f32 {
+        self.transitions()[transition.index()]
+    }
+
+    /// Match emission log-odds scores, in bits.
+    pub fn match_scores(&self) -> Vec<f32> {
+        log_odds(self.match_emissions(), &self.hmm.background_frequencies())
+    }
+
+    /// Insert emission log-odds scores, in bits.
+    pub fn insert_scores(&self) -> Vec<f32> {
+        log_odds(self.insert_emissions(), &self.hmm.background_frequencies())
+    }
+
+    /// Transition scores, in bits. Impossible transitions score -infinity.
+    pub fn transition_scores(&self) -> Vec<f32> {
+        self.transitions().iter().map(|p| p.log2()).collect()
+    }
+
+    fn alphabet_size(&self) -> usize {
+        unsafe { (*self.hmm.c_alphabet()).K as usize }
+    }
+
+    fn symbol_index(&self, symbol: char) -> Option<usize> {
+        if !symbol.is_ascii() {
+            return None;
+        }
+        // Digitise through the alphabet's input map, so that lower case
+        // symbols are accepted as in sequences.
+        let x = unsafe { (*self.hmm.c_alphabet()).inmap[symbol as usize] } as usize;
+        (x < self.alphabet_size()).then_some(x)
+    }
+
+    unsafe fn row(&self, matrix: *mut *mut f32, len: usize) -> &'a [f32] {
+        std::slice::from_raw_parts(*matrix.add(self.k), len)
+    }
+}
+
+fn log_odds(probabilities: &[f32], background: &[f32]) -> Vec<f32> {
+    probabilities
+        .iter()
+        .zip(background)
+        .map(|(p, f)| (p / f).log2())
+        .collect()
+}
@@ -1,32 +1,156 @@
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
 
-use crate::{hmm::Hmm, EaselSequence};
+use crate::{hmm::Hmm, Alphabet, EaselSequence, HmmerError};
 
-#[derive(Debug)]
-pub enum HmmerAlignError {
-    AlignmentFailure,
-    SerializationFailure,
-    NoSequences,
+/// Owned wrapper around an ESL_MSA (multiple sequence alignment).
+pub struct EaselMsa {
+    c_msa: *mut libhmmer_sys::ESL_MSA,
+    // Alphabet of a digital MSA, owned by this struct. NULL for text mode
+    // MSAs.
+    c_abc: *mut libhmmer_sys::ESL_ALPHABET,
 }
 
-impl std::fmt::Display for HmmerAlignError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            HmmerAlignError::AlignmentFailure => write!(f, "Alignment/trace computation failed"),
-            HmmerAlignError::SerializationFailure => write!(f, "Stockholm serialization failed"),
-            HmmerAlignError::NoSequences => write!(f, "No sequences provided"),
+impl EaselMsa {
+    /// Take ownership of a text mode ESL_MSA.
+    pub(crate) fn from_c_msa(c_msa: *mut libhmmer_sys::ESL_MSA) -> EaselMsa {
+        EaselMsa {
+            c_msa,
+            c_abc: std::ptr::null_mut(),
         }
     }
-}
 
-impl std::error::Error for HmmerAlignError {}
+    /// Read all alignments from a file (Stockholm, aligned FASTA, A2M, ...;
+    /// the format is autodetected) in digital mode.
+    ///
+    /// As in hmmbuild, if the file holds a single unnamed alignment, it is
+    /// named after the file, minus any suffix.
+    pub fn read_msas_from_path(
+        path: &std::path::Path,
+        alphabet: Alphabet,
+    ) -> Result<Vec<EaselMsa>, HmmerError> {
+        let msafile = CString::new(path.to_string_lossy().as_bytes()).map_err(|_| {
+            HmmerError::InvalidArgument("MSA file path must not contain null bytes".to_string())
+        })?;
 
-/// Owned wrapper around an ESL_MSA (multiple sequence alignment).
-pub struct EaselMsa {
-    c_msa: *mut libhmmer_sys::ESL_MSA,
-}
+        let mut abc = alphabet.create_c_alphabet();
+        if abc.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
+        let mut afp: *mut libhmmer_sys::ESL_MSAFILE = std::ptr::null_mut();
+        let status = unsafe {
+            libhmmer_sys::esl_msafile_Open(
+                &mut abc,
+                msafile.as_ptr(),
+                std::ptr::null(),
+                libhmmer_sys::eslMSAFILE_UNKNOWN as i32,
+                std::ptr::null_mut(),
+                &mut afp,
+            )
+        };
+        if status != libhmmer_sys::eslOK as i32 {
+            let err = if afp.is_null() {
+                HmmerError::from_status(status, "")
+            } else {
+                unsafe { HmmerError::from_errbuf(status, (*afp).errmsg.as_ptr()) }
+            };
+            unsafe {
+                libhmmer_sys::esl_msafile_Close(afp);
+                libhmmer_sys::esl_alphabet_Destroy(abc);
+            }
+            return Err(err);
+        }
+
+        let mut msas = Vec::new();
+        let result = loop {
+            let mut msa: *mut libhmmer_sys::ESL_MSA = std::ptr::null_mut();
+            let status = unsafe { libhmmer_sys::esl_msafile_Read(afp, &mut msa) };
+            if status == libhmmer_sys::eslEOF as i32 {
+                break Ok(());
+            } else if status != libhmmer_sys::eslOK as i32 {
+                break Err(unsafe { HmmerError::from_errbuf(status, (*afp).errmsg.as_ptr()) });
+            }
+            // Give each MSA an alphabet of its own, so they can be dropped
+            // independently.
+            let msa_abc = alphabet.create_c_alphabet();
+            if msa_abc.is_null() {
+                unsafe { libhmmer_sys::esl_msa_Destroy(msa) };
+                break Err(HmmerError::OutOfMemory);
+            }
+            unsafe {
+                (*msa).abc = msa_abc;
+            }
+            msas.push(EaselMsa {
+                c_msa: msa,
+                c_abc: msa_abc,
+            });
+        };
+        unsafe {
+            libhmmer_sys::esl_msafile_Close(afp);
+            libhmmer_sys::esl_alphabet_Destroy(abc);
+        }
+        result?;
+
+        if msas.len() == 1 && msas[0].name().is_none() {
+            if let Some(stem) = path.file_stem() {
+                msas[0].set_name(&stem.to_string_lossy())?;
+            }
+        }
+        Ok(msas)
+    }
+
+    /// Name of the alignment (the #=GF ID line in Stockholm format), if set.
+    pub fn name(&self) -> Option<String> {
+        let name = unsafe { (*self.c_msa).name };
+        if name.is_null() {
+            None
+        } else {
+            Some(unsafe { CStr::from_ptr(name).to_string_lossy().into_owned() })
+        }
+    }
+
+    /// Set the name of the alignment.
+    pub fn set_name(&mut self, name: &str) -> Result<(), HmmerError> {
+        let c_name = CString::new(name).map_err(|_| {
+            HmmerError::InvalidArgument("MSA name must not contain null bytes".to_string())
+        })?;
+        let status = unsafe { libhmmer_sys::esl_msa_SetName(self.c_msa, c_name.as_ptr(), -1) };
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_status(status, "Failed to set MSA name"));
+        }
+        Ok(())
+    }
+
+    /// Make a digital mode copy of this MSA that uses the given alphabet.
+    /// The caller owns the returned ESL_MSA, but not the alphabet.
+    ///
+    /// # Safety
+    /// `abc` must be a valid alphabet that outlives the copy.
+    pub(crate) unsafe fn digital_copy(
+        &self,
+        abc: *mut libhmmer_sys::ESL_ALPHABET,
+    ) -> Result<*mut libhmmer_sys::ESL_MSA, HmmerError> {
+        if !(*self.c_msa).abc.is_null() && (*(*self.c_msa).abc).type_ != (*abc).type_ {
+            return Err(HmmerError::Incompatible(
+                "MSA alphabet does not match the builder's alphabet".to_string(),
+            ));
+        }
+        let copy = libhmmer_sys::esl_msa_Clone(self.c_msa);
+        if copy.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
+        if (*copy).ax.is_null() {
+            let mut errbuf = vec![0 as libc::c_char; libhmmer_sys::eslERRBUFSIZE as usize];
+            let status = libhmmer_sys::esl_msa_Digitize(abc, copy, errbuf.as_mut_ptr());
+            if status != libhmmer_sys::eslOK as i32 {
+                libhmmer_sys::esl_msa_Destroy(copy);
+                return Err(HmmerError::from_errbuf(status, errbuf.as_ptr()));
+            }
+        } else {
+            (*copy).abc = abc;
+        }
+        Ok(copy)
+    }
 
-impl EaselMsa {
     /// Number of sequences in the alignment.
     pub fn num_sequences(&self) -> usize {
         unsafe { (*self.c_msa).nseq as usize }
@@ -46,7 +170,7 @@ impl EaselMsa {
         }
     }
 
-    /// Get the aligned sequence string for the i-th sequence (text mode).
+    /// Get the aligned sequence string for the i-th sequence.
     /// Returns the alignment row including gap characters.
     pub fn aligned_sequence(&self, i: usize) -> String {
         assert!(i < self.num_sequences(), "sequence index out of bounds");
@@ -56,15 +180,19 @@ impl EaselMsa {
                 let seq_ptr = *(*self.c_msa).aseq.add(i);
                 CStr::from_ptr(seq_ptr).to_string_lossy().into_owned()
             } else {
-                // Digital mode — need to textize first, or decode manually
-                // p7_tracealign_Seqs produces text-mode MSAs, so aseq should be set
-                panic!("MSA is in digital mode; text mode expected");
+                // Digital mode: decode residues 1..alen through the
+                // alphabet's symbol table, as esl_abc_Textize() does.
+                let ax = *(*self.c_msa).ax.add(i);
+                let sym = (*(*self.c_msa).abc).sym;
+                (1..=self.alignment_length())
+                    .map(|apos| *sym.add(*ax.add(apos) as usize) as u8 as char)
+                    .collect()
             }
         }
     }
 
     /// Serialize the MSA to Stockholm format.
-    pub fn to_stockholm(&self) -> Result<String, HmmerAlignError> {
+    pub fn to_stockholm(&self) -> Result<String, HmmerError> {
         unsafe { msa_to_stockholm(self.c_msa) }
     }
 }
@@ -73,6 +201,7 @@ impl Drop for EaselMsa {
     fn drop(&mut self) {
         unsafe {
             libhmmer_sys::esl_msa_Destroy(self.c_msa);
+            libhmmer_sys::esl_alphabet_Destroy(self.c_abc);
         }
     }
 }
@@ -92,13 +221,10 @@ impl HmmerAlign {
     ///
     /// Uses the same algorithm as the `hmmalign` command-line tool:
     /// optimal accuracy alignment via Forward/Backward/Decoding.
-    pub fn align_sequences(
-        &self,
-        sequences: &[EaselSequence],
-    ) -> Result<EaselMsa, HmmerAlignError> {
+    pub fn align_sequences(&self, sequences: &[EaselSequence]) -> Result<EaselMsa, HmmerError> {
         let nseq = sequences.len();
         if nseq == 0 {
-            return Err(HmmerAlignError::NoSequences);
+            return Err(HmmerError::NoSequences);
         }
 
         unsafe {
@@ -122,7 +248,7 @@ impl HmmerAlign {
                 for t in &traces {
                     libhmmer_sys::p7_trace_Destroy(*t);
                 }
-                return Err(HmmerAlignError::AlignmentFailure);
+                return Err(HmmerError::AlignmentFailure);
             }
 
             let mut msa: *mut libhmmer_sys::ESL_MSA = std::ptr::null_mut();
@@ -141,10 +267,10 @@ impl HmmerAlign {
             }
 
             if align_status != libhmmer_sys::eslOK as i32 {
-                return Err(HmmerAlignError::AlignmentFailure);
+                return Err(HmmerError::AlignmentFailure);
             }
 
-            Ok(EaselMsa { c_msa: msa })
+            Ok(EaselMsa::from_c_msa(msa))
         }
     }
 
@@ -153,19 +279,19 @@ impl HmmerAlign {
     pub fn align_sequences_into_stockholm(
         &self,
         sequences: &[EaselSequence],
-    ) -> Result<String, HmmerAlignError> {
+    ) -> Result<String, HmmerError> {
         let msa = self.align_sequences(sequences)?;
         msa.to_stockholm()
     }
 }
 
 /// Serialize an ESL_MSA to Stockholm format string using open_memstream.
-unsafe fn msa_to_stockholm(msa: *mut libhmmer_sys::ESL_MSA) -> Result<String, HmmerAlignError> {
+unsafe fn msa_to_stockholm(msa: *mut libhmmer_sys::ESL_MSA) -> Result<String, HmmerError> {
     let mut buf: *mut libc::c_char = std::ptr::null_mut();
     let mut buf_size: libc::size_t = 0;
     let fp = libc::open_memstream(&mut buf, &mut buf_size);
     if fp.is_null() {
-        return Err(HmmerAlignError::SerializationFailure);
+        return Err(HmmerError::SerializationFailure);
     }
 
     let write_status = libhmmer_sys::esl_msafile_Write(
@@ -179,7 +305,7 @@ unsafe fn msa_to_stockholm(msa: *mut libhmmer_sys::ESL_MSA) -> Result<String, Hm
         if !buf.is_null() {
             libc::free(buf as *mut libc::c_void);
         }
-        return Err(HmmerAlignError::SerializationFailure);
+        return Err(HmmerError::SerializationFailure);
     }
 
     let result = if !buf.is_null() && buf_size > 0 {
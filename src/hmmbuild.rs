@@ -0,0 +1,352 @@
+use log::*;
+
+use std::ffi::CString;
+
+use crate::{hmm::Hmm, libhmmer_sys_extras::*, Alphabet, EaselMsa, EaselSequence, HmmerError};
+
+/// Model architecture construction, i.e. which alignment columns are
+/// assigned as consensus (match) columns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchStrategy {
+    /// Columns with a residue fraction >= symfrac are consensus (hmmbuild
+    /// --fast). Default.
+    Fast,
+    /// Consensus columns are those marked in the alignment's RF annotation
+    /// line (hmmbuild --hand).
+    Hand,
+}
+
+/// Relative sequence weighting scheme.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeightStrategy {
+    /// Henikoff position-based weights (hmmbuild --wpb). Default.
+    PositionBased,
+    /// Gerstein/Sonnhammer/Chothia tree weights (hmmbuild --wgsc).
+    Gsc,
+    /// Henikoff simple filter weights at the given identity threshold
+    /// (hmmbuild --wblosum --wid).
+    Blosum { wid: f64 },
+    /// All sequences get weight 1 (hmmbuild --wnone).
+    None,
+    /// Use the weights given in the alignment file (hmmbuild --wgiven).
+    Given,
+}
+
+/// Method for determining the effective sequence number.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EffnStrategy {
+    /// Adjust effective sequence number to achieve relative entropy target
+    /// (hmmbuild --eent). Default.
+    Entropy,
+    /// Adjust effective sequence number to achieve relative entropy target,
+    /// using exponential scaling (hmmbuild --eentexp).
+    EntropyExp,
+    /// Effective sequence number is the number of single linkage clusters at
+    /// the given identity threshold (hmmbuild --eclust --eid).
+    Clust { eid: f64 },
+    /// No effective sequence number weighting; just use nseq (hmmbuild
+    /// --enone).
+    None,
+    /// Set the effective sequence number directly (hmmbuild --eset).
+    Set(f64),
+}
+
+/// Prior used when parameterizing the model from counts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PriorStrategy {
+    /// Dirichlet mixture prior appropriate to the alphabet. Default.
+    Default,
+    /// Laplace +1 prior (hmmbuild --plaplace).
+    Laplace,
+    /// No prior; maximum likelihood parameters (hmmbuild --pnone).
+    None,
+}
+
+/// Builds calibrated profile HMMs from multiple sequence alignments, as the
+/// `hmmbuild` command-line tool does.
+pub struct HmmBuilder {
+    bld: *mut libhmmer_sys::P7_BUILDER,
+    bg: *mut libhmmer_sys::P7_BG,
+    abc: *mut libhmmer_sys::ESL_ALPHABET,
+    alphabet: Alphabet,
+}
+
+impl HmmBuilder {
+    /// Create a builder with hmmbuild's default options.
+    pub fn new(alphabet: Alphabet) -> Result<HmmBuilder, HmmerError> {
+        let abc = alphabet.create_c_alphabet();
+        if abc.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
+        let bg = unsafe { libhmmer_sys::p7_bg_Create(abc) };
+        let bld = unsafe { libhmmer_sys::p7_builder_Create(std::ptr::null(), abc) };
+        if bg.is_null() || bld.is_null() {
+            unsafe {
+                libhmmer_sys::p7_builder_Destroy(bld);
+                libhmmer_sys::p7_bg_Destroy(bg);
+                libhmmer_sys::esl_alphabet_Destroy(abc);
+            }
+            return Err(HmmerError::OutOfMemory);
+        }
+        debug!("Builder created successfully");
+
+        // bld->w_len      = (go != NULL && esl_opt_IsOn (go, "--w_length")) ?  esl_opt_GetInteger(go, "--w_length"): -1;
+        // bld->w_beta     = (go != NULL && esl_opt_IsOn (go, "--w_beta"))   ?  esl_opt_GetReal   (go, "--w_beta")    : p7_DEFAULT_WINDOW_BETA;
+        unsafe {
+            (*bld).w_len = -1;
+            (*bld).w_beta = p7_DEFAULT_WINDOW_BETA;
+        }
+
+        Ok(HmmBuilder {
+            bld,
+            bg,
+            abc,
+            alphabet,
+        })
+    }
+
+    pub(crate) fn alphabet(&self) -> Alphabet {
+        self.alphabet
+    }
+
+    /// Set the model architecture construction method. Default: Fast
+    pub fn with_arch_strategy(self, arch: ArchStrategy) -> Self {
+        unsafe {
+            (*self.bld).arch_strategy = match arch {
+                ArchStrategy::Fast => p7_ARCH_FAST,
+                ArchStrategy::Hand => p7_ARCH_HAND,
+            };
+        }
+        self
+    }
+
+    /// Set the residue fraction above which a column is assigned as
+    /// consensus, with ArchStrategy::Fast (hmmbuild --symfrac). Default: 0.5
+    pub fn with_symfrac(self, symfrac: f32) -> Self {
+        unsafe {
+            (*self.bld).symfrac = symfrac;
+        }
+        self
+    }
+
+    /// Set the fraction of the alignment length below which a sequence is
+    /// called a fragment (hmmbuild --fragthresh). Default: 0.5
+    pub fn with_fragthresh(self, fragthresh: f32) -> Self {
+        unsafe {
+            (*self.bld).fragthresh = fragthresh;
+        }
+        self
+    }
+
+    /// Set the relative sequence weighting scheme. Default: PositionBased
+    pub fn with_wgt_strategy(self, wgt: WeightStrategy) -> Self {
+        unsafe {
+            (*self.bld).wgt_strategy = match wgt {
+                WeightStrategy::PositionBased => p7_WGT_PB,
+                WeightStrategy::Gsc => p7_WGT_GSC,
+                WeightStrategy::Blosum { wid } => {
+                    (*self.bld).wid = wid;
+                    p7_WGT_BLOSUM
+                }
+                WeightStrategy::None => p7_WGT_NONE,
+                WeightStrategy::Given => p7_WGT_GIVEN,
+            };
+        }
+        self
+    }
+
+    /// Set the effective sequence number method. Default: Entropy
+    pub fn with_effn_strategy(self, effn: EffnStrategy) -> Self {
+        unsafe {
+            (*self.bld).effn_strategy = match effn {
+                EffnStrategy::Entropy => p7_EFFN_ENTROPY,
+                EffnStrategy::EntropyExp => p7_EFFN_ENTROPY_EXP,
+                EffnStrategy::Clust { eid } => {
+                    (*self.bld).eid = eid;
+                    p7_EFFN_CLUST
+                }
+                EffnStrategy::None => p7_EFFN_NONE,
+                EffnStrategy::Set(eset) => {
+                    (*self.bld).eset = eset;
+                    p7_EFFN_SET
+                }
+            };
+        }
+        self
+    }
+
+    /// Set the prior used to parameterize the model. Default: Default
+    pub fn with_prior(self, prior: PriorStrategy) -> Result<Self, HmmerError> {
+        unsafe {
+            let new_prior = match prior {
+                PriorStrategy::Default => match self.alphabet {
+                    Alphabet::Protein => libhmmer_sys::p7_prior_CreateAmino(),
+                    Alphabet::DNA | Alphabet::RNA => libhmmer_sys::p7_prior_CreateNucleic(),
+                },
+                PriorStrategy::Laplace => libhmmer_sys::p7_prior_CreateLaplace(self.abc),
+                PriorStrategy::None => std::ptr::null_mut(),
+            };
+            if new_prior.is_null() && prior != PriorStrategy::None {
+                return Err(HmmerError::OutOfMemory);
+            }
+            libhmmer_sys::p7_prior_Destroy((*self.bld).prior);
+            (*self.bld).prior = new_prior;
+        }
+        Ok(self)
+    }
+
+    /// Set the substitution score matrix and gap open/extend probabilities
+    /// used by [`HmmBuilder::build_from_sequence`] (phmmer --mx, --popen,
+    /// --pextend). `matrix` is the name of one of Easel's built-in matrices,
+    /// e.g. "BLOSUM62", "PAM30" or "DNA1".
+    ///
+    /// Default: BLOSUM62, 0.02, 0.4 for protein, as phmmer; DNA1, 0.03125,
+    /// 0.75 for nucleotides, as nhmmer.
+    pub fn with_score_system(
+        self,
+        matrix: &str,
+        popen: f64,
+        pextend: f64,
+    ) -> Result<Self, HmmerError> {
+        self.load_score_system(matrix, popen, pextend)?;
+        Ok(self)
+    }
+
+    fn load_score_system(&self, matrix: &str, popen: f64, pextend: f64) -> Result<(), HmmerError> {
+        let c_matrix = CString::new(matrix).map_err(|_| {
+            HmmerError::InvalidArgument("Matrix name must not contain null bytes".to_string())
+        })?;
+        let status = unsafe {
+            libhmmer_sys::p7_builder_LoadScoreSystem(
+                self.bld,
+                c_matrix.as_ptr(),
+                popen,
+                pextend,
+                self.bg,
+            )
+        };
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(unsafe { HmmerError::from_errbuf(status, (*self.bld).errbuf.as_ptr()) });
+        }
+        debug!("Score system {} loaded successfully", matrix);
+        Ok(())
+    }
+
+    /// Build a profile HMM from a single sequence, using a substitution score
+    /// matrix as phmmer does. The HMM is named after the sequence, and has
+    /// its E-value parameters calibrated, so it can be given to
+    /// [`crate::HmmerPipeline::new`] directly.
+    pub fn build_from_sequence(&mut self, seq: &EaselSequence) -> Result<Hmm, HmmerError> {
+        self.single_build(seq, std::ptr::null_mut())
+    }
+
+    /// As build_from_sequence(), also returning the faux trace of the
+    /// sequence through the model in `opt_tr`, if it is not NULL. The caller
+    /// owns the trace.
+    pub(crate) fn single_build(
+        &mut self,
+        seq: &EaselSequence,
+        opt_tr: *mut *mut libhmmer_sys::P7_TRACE,
+    ) -> Result<Hmm, HmmerError> {
+        unsafe {
+            if (*(*seq.c_sq).abc).type_ != (*self.abc).type_ {
+                return Err(HmmerError::Incompatible(
+                    "Sequence alphabet does not match the builder's alphabet".to_string(),
+                ));
+            }
+            if (*self.bld).Q.is_null() {
+                match self.alphabet {
+                    Alphabet::Protein => self.load_score_system("BLOSUM62", 0.02, 0.4)?,
+                    Alphabet::DNA | Alphabet::RNA => {
+                        self.load_score_system("DNA1", 0.03125, 0.75)?
+                    }
+                }
+            }
+        }
+
+        // p7_SingleBuilder() creates the HMM with the builder's alphabet.
+        // Swap in an alphabet of the same type for the HMM to own, so that
+        // the HMM outlives this builder.
+        let abc = self.alphabet.create_c_alphabet();
+        if abc.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
+        let mut hmm: *mut libhmmer_sys::P7_HMM = std::ptr::null_mut();
+        let status = unsafe {
+            (*self.bld).abc = abc;
+            let status = libhmmer_sys::p7_SingleBuilder(
+                self.bld,
+                seq.c_sq,
+                self.bg,
+                &mut hmm,
+                opt_tr,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            );
+            (*self.bld).abc = self.abc;
+            status
+        };
+        if status != libhmmer_sys::eslOK as i32 {
+            unsafe { libhmmer_sys::esl_alphabet_Destroy(abc) };
+            return Err(unsafe { HmmerError::from_errbuf(status, (*self.bld).errbuf.as_ptr()) });
+        }
+        debug!("Single sequence HMM built successfully");
+
+        Ok(Hmm { c_hmm: hmm })
+    }
+
+    /// Build a profile HMM from an alignment. The alignment must be named
+    /// (see [`EaselMsa::set_name`]), since the name is given to the HMM. The
+    /// returned HMM has its E-value parameters calibrated, so is ready for
+    /// searching.
+    pub fn build(&mut self, msa: &EaselMsa) -> Result<Hmm, HmmerError> {
+        // p7_Builder() reweights and marks fragments in the MSA it is given,
+        // and the HMM it creates points to the MSA's alphabet. Build from a
+        // digital copy that uses an alphabet of its own, so the caller's
+        // alignment is left unchanged and the HMM outlives this builder.
+        let abc = self.alphabet.create_c_alphabet();
+        if abc.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
+        let copy = unsafe { msa.digital_copy(abc) };
+        let copy = match copy {
+            Ok(copy) => copy,
+            Err(e) => {
+                unsafe { libhmmer_sys::esl_alphabet_Destroy(abc) };
+                return Err(e);
+            }
+        };
+
+        let mut hmm: *mut libhmmer_sys::P7_HMM = std::ptr::null_mut();
+        let status = unsafe {
+            libhmmer_sys::p7_Builder(
+                self.bld,
+                copy,
+                self.bg,
+                &mut hmm,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        unsafe { libhmmer_sys::esl_msa_Destroy(copy) };
+        if status != libhmmer_sys::eslOK as i32 {
+            unsafe { libhmmer_sys::esl_alphabet_Destroy(abc) };
+            return Err(unsafe { HmmerError::from_errbuf(status, (*self.bld).errbuf.as_ptr()) });
+        }
+        debug!("HMM built successfully");
+
+        Ok(Hmm { c_hmm: hmm })
+    }
+}
+
+impl Drop for HmmBuilder {
+    fn drop(&mut self) {
+        unsafe {
+            libhmmer_sys::p7_builder_Destroy(self.bld);
+            libhmmer_sys::p7_bg_Destroy(self.bg);
+            libhmmer_sys::esl_alphabet_Destroy(self.abc);
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use log::*;
+
+use crate::{libhmmer_sys_extras::*, EaselMsa, EaselSequence, Hmm, HmmerError, Trace};
+
+/// Alignment mode of the profile that sequences are sampled from in profile
+/// mode, as chosen by hmmemit's --local, --unilocal, --glocal and --uniglocal
+/// options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileMode {
+    /// Multihit local ("fs" mode). The hmmemit default.
+    Local,
+    /// Unihit local ("sw" mode).
+    Unilocal,
+    /// Multihit glocal ("ls" mode).
+    Glocal,
+    /// Unihit glocal ("s" mode).
+    Uniglocal,
+}
+
+impl ProfileMode {
+    fn c_mode(self) -> i32 {
+        match self {
+            ProfileMode::Local => p7_LOCAL,
+            ProfileMode::Unilocal => p7_UNILOCAL,
+            ProfileMode::Glocal => p7_GLOCAL,
+            ProfileMode::Uniglocal => p7_UNIGLOCAL,
+        }
+    }
+}
+
+/// Samples sequences from an HMM, as the `hmmemit` command-line tool does.
+///
+/// By default sequences are sampled from the core model (p7_CoreEmit), so
+/// they are global matches to the model without flanking sequence. Use
+/// [`HmmEmitter::with_profile_mode`] to sample from a configured profile
+/// instead (p7_ProfileEmit, hmmemit -p).
+pub struct HmmEmitter<'a> {
+    hmm: &'a Hmm,
+    rng: *mut libhmmer_sys::ESL_RANDOMNESS,
+    // Profile mode and expected length, or None for core emission
+    profile: Option<(ProfileMode, u32)>,
+    // Number of sequences sampled so far, used to name them
+    nemitted: usize,
+}
+
+impl<'a> HmmEmitter<'a> {
+    /// Create an emitter for the given HMM, with its random number generator
+    /// seeded by `seed`. The same seed gives the same sequences. A seed of 0
+    /// asks Easel to choose an arbitrary seed instead.
+    pub fn new(hmm: &'a Hmm, seed: u32) -> Result<Self, HmmerError> {
+        // r = esl_randomness_Create(esl_opt_GetInteger(go, "--seed"));
+        let rng = unsafe { libhmmer_sys::esl_randomness_Create(seed) };
+        if rng.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
+        Ok(HmmEmitter {
+            hmm,
+            rng,
+            profile: None,
+            nemitted: 0,
+        })
+    }
+
+    /// Sample sequences from the profile rather than the core model, with
+    /// the profile configured in `mode` for an expected sequence length of
+    /// `length` (hmmemit -p, which defaults to local mode and -L 400).
+    /// Sampled sequences then include flanking nonhomologous sequence and,
+    /// in multihit modes, possibly several hits to the model.
+    pub fn with_profile_mode(mut self, mode: ProfileMode, length: u32) -> Self {
+        self.profile = Some((mode, length));
+        self
+    }
+
+    /// Sample `n` sequences. They are named "<hmm name>-sample<i>", where i
+    /// counts the sequences sampled by this emitter, starting from 1.
+    pub fn emit_sequences(&mut self, n: usize) -> Result<Vec<EaselSequence>, HmmerError> {
+        match self.profile {
+            None => (0..n).map(|_| self.emit_core(None)).collect(),
+            Some((mode, length)) => {
+                let length = i32::try_from(length).map_err(|_| {
+                    HmmerError::InvalidArgument(format!(
+                        "Expected sequence length {length} is too large"
+                    ))
+                })?;
+                self.emit_from_profile(n, mode, length)
+            }
+        }
+    }
+
+    /// Sample `n` sequences from the core model and return them aligned to
+    /// it, as hmmemit -a does. All consensus columns are included in the
+    /// alignment, even if no sampled sequence has a residue in them. The
+    /// profile mode, if set, does not apply here.
+    pub fn emit_alignment(&mut self, n: usize) -> Result<EaselMsa, HmmerError> {
+        if n == 0 {
+            return Err(HmmerError::NoSequences);
+        }
+
+        let mut sequences = Vec::with_capacity(n);
+        let mut traces = Vec::with_capacity(n);
+        for _ in 0..n {
+            let trace = Trace::new()?;
+            sequences.push(self.emit_core(Some(&trace))?);
+            traces.push(trace);
+        }
+
+        let mut sq_ptrs: Vec<*mut libhmmer_sys::ESL_SQ> =
+            sequences.iter().map(|s| s.c_sq).collect();
+        let mut tr_ptrs: Vec<*mut libhmmer_sys::P7_TRACE> = traces.iter().map(|t| t.0).collect();
+
+        // p7_tracealign_Seqs(sq, tr, N, hmm->M, optflags, hmm, &msa);
+        let mut msa: *mut libhmmer_sys::ESL_MSA = std::ptr::null_mut();
+        let status = unsafe {
+            libhmmer_sys::p7_tracealign_Seqs(
+                sq_ptrs.as_mut_ptr(),
+                tr_ptrs.as_mut_ptr(),
+                n as i32,
+                (*self.hmm.c_hmm).M,
+                p7_ALL_CONSENSUS_COLS,
+                self.hmm.c_hmm,
+                &mut msa,
+            )
+        };
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::AlignmentFailure);
+        }
+        debug!("Aligned {} sequences emitted from {}", n, self.hmm.name());
+
+        Ok(EaselMsa::from_c_msa(msa))
+    }
+
+    fn emit_core(&mut self, trace: Option<&Trace>) -> Result<EaselSequence, HmmerError> {
+        let mut sq = self.new_sequence()?;
+        let tr = trace.map_or(std::ptr::null_mut(), |t| t.0);
+        // p7_CoreEmit(r, hmm, sq, tr)
+        let status = unsafe { libhmmer_sys::p7_CoreEmit(self.rng, self.hmm.c_hmm, sq.c_sq, tr) };
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_status(status, "Failed to emit sequence"));
+        }
+        self.name_sample(&mut sq)?;
+        Ok(sq)
+    }
+
+    fn emit_from_profile(
+        &mut self,
+        n: usize,
+        mode: ProfileMode,
+        length: i32,
+    ) -> Result<Vec<EaselSequence>, HmmerError> {
+        let c_hmm = self.hmm.c_hmm;
+        let abc = self.hmm.c_alphabet();
+
+        // bg = p7_bg_Create(hmm->abc);
+        // gm = p7_profile_Create(hmm->M, hmm->abc);
+        let bg = unsafe { libhmmer_sys::p7_bg_Create(abc) };
+        let gm = unsafe { libhmmer_sys::p7_profile_Create((*c_hmm).M, abc) };
+        let result = if bg.is_null() || gm.is_null() {
+            Err(HmmerError::OutOfMemory)
+        } else {
+            unsafe { self.emit_from_configured_profile(n, mode, length, bg, gm) }
+        };
+        unsafe {
+            libhmmer_sys::p7_profile_Destroy(gm);
+            libhmmer_sys::p7_bg_Destroy(bg);
+        }
+        result
+    }
+
+    unsafe fn emit_from_configured_profile(
+        &mut self,
+        n: usize,
+        mode: ProfileMode,
+        length: i32,
+        bg: *mut libhmmer_sys::P7_BG,
+        gm: *mut libhmmer_sys::P7_PROFILE,
+    ) -> Result<Vec<EaselSequence>, HmmerError> {
+        // p7_ProfileConfig(hmm, bg, gm, L, mode);
+        // p7_bg_SetLength(bg, L);
+        let mut status =
+            libhmmer_sys::p7_ProfileConfig(self.hmm.c_hmm, bg, gm, length, mode.c_mode());
+        if status == libhmmer_sys::eslOK as i32 {
+            status = libhmmer_sys::p7_bg_SetLength(bg, length);
+        }
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_status(
+                status,
+                "Failed to configure profile",
+            ));
+        }
+
+        let mut sequences = Vec::with_capacity(n);
+        for _ in 0..n {
+            let mut sq = self.new_sequence()?;
+            // p7_ProfileEmit(r, hmm, gm, bg, sq, tr);
+            let status = libhmmer_sys::p7_ProfileEmit(
+                self.rng,
+                self.hmm.c_hmm,
+                gm,
+                bg,
+                sq.c_sq,
+                std::ptr::null_mut(),
+            );
+            if status != libhmmer_sys::eslOK as i32 {
+                return Err(HmmerError::from_status(status, "Failed to emit sequence"));
+            }
+            self.name_sample(&mut sq)?;
+            sequences.push(sq);
+        }
+        Ok(sequences)
+    }
+
+    fn new_sequence(&self) -> Result<EaselSequence, HmmerError> {
+        new_digital_sequence(self.hmm)
+    }
+
+    // esl_sq_FormatName(sq, "%s-sample%d", hmm->name, nseq);
+    fn name_sample(&mut self, sq: &mut EaselSequence) -> Result<(), HmmerError> {
+        self.nemitted += 1;
+        sq.set_name(&format!("{}-sample{}", self.hmm.name(), self.nemitted))
+    }
+}
+
+impl Drop for HmmEmitter<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            libhmmer_sys::esl_randomness_Destroy(self.rng);
+        }
+    }
+}
+
+impl Hmm {
+    /// The majority-rule consensus sequence of the model: the most probable
+    /// residue of each match state, as hmmemit -c emits it. Masked positions
+    /// are given the unknown residue. The sequence is named
+    /// "<hmm name>-consensus".
+    ///
+    /// Unlike [`Hmm::consensus`], which returns the CONS annotation line
+    /// stored in the model file, this is always calculated from the match
+    /// emission probabilities.
+    pub fn consensus_sequence(&self) -> Result<EaselSequence, HmmerError> {
+        let mut sq = new_digital_sequence(self)?;
+        // p7_emit_SimpleConsensus(hmm, sq)
+        let status = unsafe { libhmmer_sys::p7_emit_SimpleConsensus(self.c_hmm, sq.c_sq) };
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_status(
+                status,
+                "Failed to create simple consensus sequence",
+            ));
+        }
+        sq.set_name(&format!("{}-consensus", self.name()))?;
+        Ok(sq)
+    }
+
+    /// The consensus sequence of the model with the strength of each
+    /// position shown by case, as hmmemit -C emits it. The most probable
+    /// residue of each match state is given in upper case if its
+    /// probability is at least `min_upper`, in lower case otherwise, and as
+    /// a lower case unknown residue ('x' for protein, 'n' for nucleic acid)
+    /// if it is below `min_lower`. hmmemit's defaults for both are 0.0.
+    ///
+    /// This is returned as text rather than an [`EaselSequence`], since the
+    /// case would be lost in digital mode.
+    pub fn fancy_consensus_sequence(
+        &self,
+        min_lower: f32,
+        min_upper: f32,
+    ) -> Result<String, HmmerError> {
+        unsafe {
+            // sq = esl_sq_Create()
+            let sq = libhmmer_sys::esl_sq_Create();
+            if sq.is_null() {
+                return Err(HmmerError::OutOfMemory);
+            }
+            // p7_emit_FancyConsensus(hmm, minl, minu, sq)
+            let status = libhmmer_sys::p7_emit_FancyConsensus(self.c_hmm, min_lower, min_upper, sq);
+            let result = if status != libhmmer_sys::eslOK as i32 {
+                Err(HmmerError::from_status(
+                    status,
+                    "Failed to create fancy consensus sequence",
+                ))
+            } else {
+                Ok(std::ffi::CStr::from_ptr((*sq).seq)
+                    .to_string_lossy()
+                    .into_owned())
+            };
+            libhmmer_sys::esl_sq_Destroy(sq);
+            result
+        }
+    }
+}
+
+/// A new, empty digital sequence in the alphabet of the HMM. The sequence
+/// gets its own copy of the alphabet, so that it does not borrow the HMM.
+fn new_digital_sequence(hmm: &Hmm) -> Result<EaselSequence, HmmerError> {
+    unsafe {
+        let abc = libhmmer_sys::esl_alphabet_Create((*hmm.c_alphabet()).type_);
+        if abc.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
+        let c_sq = libhmmer_sys::esl_sq_CreateDigital(abc);
+        if c_sq.is_null() {
+            libhmmer_sys::esl_alphabet_Destroy(abc);
+            return Err(HmmerError::OutOfMemory);
+        }
+        Ok(EaselSequence { c_sq })
+    }
+}
@@ -0,0 +1,297 @@
+use log::*;
+use std::ffi::CString;
+
+use crate::{libhmmer_sys_extras::*, Hmm, HmmerError};
+
+impl Hmm {
+    /// Press HMMs into a binary database for fast scanning, as `hmmpress`
+    /// does. Four files are written next to `path`: `<path>.h3m` (binary
+    /// models), `<path>.h3i` (SSI index of names and accessions), `<path>.h3f`
+    /// (MSV filter parts of the optimized profiles) and `<path>.h3p` (the
+    /// remainder of the profiles). `path` itself does not need to exist.
+    ///
+    /// Existing pressed files are replaced. If pressing fails, any partially
+    /// written files are removed.
+    pub fn press_hmms_to_path(hmms: &[Hmm], path: &std::path::Path) -> Result<(), HmmerError> {
+        let first = hmms.first().ok_or_else(|| {
+            HmmerError::InvalidArgument("At least one HMM is required to press".to_string())
+        })?;
+
+        let mut dbf = DbFiles::open(path)?;
+
+        let fname = path_to_cstring(path.to_string_lossy().into_owned())?;
+        let mut fh: u16 = 0;
+        // esl_newssi_AddFile(dbf->nssi, hfp->fname, 0, &fh); /* 0 = format code (HMMs don't have any yet) */
+        let status =
+            unsafe { libhmmer_sys::esl_newssi_AddFile(dbf.nssi, fname.as_ptr(), 0, &mut fh) };
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_status(
+                status,
+                format!("Failed to add HMM file {} to new SSI index", path.display()),
+            ));
+        }
+
+        // bg = p7_bg_Create(abc);
+        // p7_bg_SetLength(bg, 400);
+        let abc = first.c_alphabet();
+        let bg = unsafe { libhmmer_sys::p7_bg_Create(abc) };
+        if bg.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
+        unsafe { libhmmer_sys::p7_bg_SetLength(bg, 400) };
+
+        let result = hmms
+            .iter()
+            .enumerate()
+            .try_for_each(|(i, hmm)| unsafe { dbf.press_one(hmm, i, bg, fh) });
+        unsafe { libhmmer_sys::p7_bg_Destroy(bg) };
+        result?;
+
+        // esl_newssi_Write(dbf->nssi);
+        let status = unsafe { libhmmer_sys::esl_newssi_Write(dbf.nssi) };
+        if status != libhmmer_sys::eslOK as i32 {
+            let errbuf = unsafe { (*dbf.nssi).errbuf.as_ptr() };
+            return Err(unsafe { HmmerError::from_errbuf(status, errbuf) });
+        }
+        dbf.close_files()?;
+        debug!("Pressed {} HMMs into {}", hmms.len(), path.display());
+
+        dbf.finished = true;
+        Ok(())
+    }
+}
+
+/// The four output files of a pressed database. Mirrors hmmpress's
+/// open_dbfiles()/close_dbfiles(): the files are closed on drop if they are
+/// still open, and removed unless `finished` was set.
+struct DbFiles {
+    mfile: CString,
+    ffile: CString,
+    pfile: CString,
+    mfp: *mut libc::FILE,
+    ffp: *mut libc::FILE,
+    pfp: *mut libc::FILE,
+    nssi: *mut libhmmer_sys::ESL_NEWSSI,
+    finished: bool,
+}
+
+impl DbFiles {
+    fn open(path: &std::path::Path) -> Result<DbFiles, HmmerError> {
+        let basename = path.to_string_lossy();
+        let ssifile = path_to_cstring(format!("{basename}.h3i"))?;
+        let mut dbf = DbFiles {
+            mfile: path_to_cstring(format!("{basename}.h3m"))?,
+            ffile: path_to_cstring(format!("{basename}.h3f"))?,
+            pfile: path_to_cstring(format!("{basename}.h3p"))?,
+            mfp: std::ptr::null_mut(),
+            ffp: std::ptr::null_mut(),
+            pfp: std::ptr::null_mut(),
+            nssi: std::ptr::null_mut(),
+            finished: false,
+        };
+
+        // status = esl_newssi_Open(dbf->ssifile, allow_overwrite, &(dbf->nssi));
+        let status = unsafe { libhmmer_sys::esl_newssi_Open(ssifile.as_ptr(), 1, &mut dbf.nssi) };
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_status(
+                status,
+                format!("Failed to create SSI index {basename}.h3i"),
+            ));
+        }
+
+        // if ((dbf->mfp = fopen(dbf->mfile, "wb")) == NULL)  ESL_XFAIL(eslEWRITE, errbuf, "Failed to open binary HMM file %s for writing",        dbf->mfile);
+        // if ((dbf->ffp = fopen(dbf->ffile, "wb")) == NULL)  ESL_XFAIL(eslEWRITE, errbuf, "Failed to open binary MSV filter file %s for writing", dbf->ffile);
+        // if ((dbf->pfp = fopen(dbf->pfile, "wb")) == NULL)  ESL_XFAIL(eslEWRITE, errbuf, "Failed to open binary profile file %s for writing",    dbf->pfile);
+        for (fp, file) in [
+            (&mut dbf.mfp, &dbf.mfile),
+            (&mut dbf.ffp, &dbf.ffile),
+            (&mut dbf.pfp, &dbf.pfile),
+        ] {
+            *fp = unsafe { libc::fopen(file.as_ptr(), c"wb".as_ptr()) };
+            if fp.is_null() {
+                return Err(HmmerError::Write(format!(
+                    "Failed to open {} for writing",
+                    file.to_string_lossy()
+                )));
+            }
+        }
+
+        Ok(dbf)
+    }
+
+    /// Close the binary files, so that a failure to flush them is reported
+    /// rather than lost on drop.
+    fn close_files(&mut self) -> Result<(), HmmerError> {
+        for (fp, file) in [
+            (&mut self.mfp, &self.mfile),
+            (&mut self.ffp, &self.ffile),
+            (&mut self.pfp, &self.pfile),
+        ] {
+            let status = unsafe { libc::fclose(std::mem::replace(fp, std::ptr::null_mut())) };
+            if status != 0 {
+                return Err(HmmerError::Write(format!(
+                    "Failed to write {}: {}",
+                    file.to_string_lossy(),
+                    std::io::Error::last_os_error()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert one HMM to an optimized profile and append it, and its index
+    /// keys, to the database. This is the body of the main loop of hmmpress.
+    unsafe fn press_one(
+        &mut self,
+        hmm: &Hmm,
+        index: usize,
+        bg: *mut libhmmer_sys::P7_BG,
+        fh: u16,
+    ) -> Result<(), HmmerError> {
+        let c_hmm = hmm.c_hmm;
+        if (*c_hmm).name.is_null() {
+            return Err(HmmerError::InvalidArgument(format!(
+                "Every HMM must have a name to be indexed. Failed to find name of HMM #{}",
+                index + 1
+            )));
+        }
+        if (*hmm.c_alphabet()).type_ != (*(*bg).abc).type_ {
+            return Err(HmmerError::Incompatible(format!(
+                "HMM {} has a different alphabet to the first HMM",
+                hmm.name()
+            )));
+        }
+
+        // gm = p7_profile_Create(hmm->M, abc);
+        // p7_ProfileConfig(hmm, bg, gm, 400, p7_LOCAL);
+        // om = p7_oprofile_Create(gm->M, abc);
+        // p7_oprofile_Convert(gm, om);
+        let abc = hmm.c_alphabet();
+        let gm = libhmmer_sys::p7_profile_Create((*c_hmm).M, abc);
+        let om = libhmmer_sys::p7_oprofile_Create((*c_hmm).M, abc);
+        if gm.is_null() || om.is_null() {
+            libhmmer_sys::p7_profile_Destroy(gm);
+            libhmmer_sys::p7_oprofile_Destroy(om);
+            return Err(HmmerError::OutOfMemory);
+        }
+        let mut status = libhmmer_sys::p7_ProfileConfig(c_hmm, bg, gm, 400, p7_LOCAL);
+        if status == libhmmer_sys::eslOK as i32 {
+            status = libhmmer_sys::p7_oprofile_Convert(gm, om);
+        }
+        libhmmer_sys::p7_profile_Destroy(gm);
+        let result = if status != libhmmer_sys::eslOK as i32 {
+            Err(HmmerError::from_status(
+                status,
+                format!("Failed to convert HMM {} to a profile", hmm.name()),
+            ))
+        } else {
+            self.write_one(c_hmm, om, fh)
+        };
+        libhmmer_sys::p7_oprofile_Destroy(om);
+        result
+    }
+
+    unsafe fn write_one(
+        &mut self,
+        c_hmm: *mut libhmmer_sys::P7_HMM,
+        om: *mut libhmmer_sys::P7_OPROFILE,
+        fh: u16,
+    ) -> Result<(), HmmerError> {
+        // om->offs[p7_MOFFSET] = ftello(dbf->mfp);
+        // om->offs[p7_FOFFSET] = ftello(dbf->ffp);
+        // om->offs[p7_POFFSET] = ftello(dbf->pfp);
+        for (offset, fp) in [
+            (p7_MOFFSET, self.mfp),
+            (p7_FOFFSET, self.ffp),
+            (p7_POFFSET, self.pfp),
+        ] {
+            let pos = libc::ftello(fp);
+            if pos == -1 {
+                return Err(HmmerError::from_status(
+                    libhmmer_sys::eslESYS as i32,
+                    "Failed to ftello() current disk position of pressed db file",
+                ));
+            }
+            (*om).offs[offset] = pos;
+        }
+
+        // esl_newssi_AddKey(dbf->nssi, hmm->name, fh, om->offs[p7_MOFFSET], 0, 0);
+        // if (hmm->acc) esl_newssi_AddAlias(dbf->nssi, hmm->acc, hmm->name);
+        let status = libhmmer_sys::esl_newssi_AddKey(
+            self.nssi,
+            (*c_hmm).name,
+            fh,
+            (*om).offs[p7_MOFFSET],
+            0,
+            0,
+        );
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_errbuf(
+                status,
+                (*self.nssi).errbuf.as_ptr(),
+            ));
+        }
+        if !(*c_hmm).acc.is_null() {
+            let status = libhmmer_sys::esl_newssi_AddAlias(self.nssi, (*c_hmm).acc, (*c_hmm).name);
+            if status != libhmmer_sys::eslOK as i32 {
+                return Err(HmmerError::from_errbuf(
+                    status,
+                    (*self.nssi).errbuf.as_ptr(),
+                ));
+            }
+        }
+
+        // p7_hmmfile_WriteBinary(dbf->mfp, -1, hmm);
+        // p7_oprofile_Write(dbf->ffp, dbf->pfp, om);
+        let status =
+            libhmmer_sys::p7_hmmfile_WriteBinary(self.mfp as *mut libhmmer_sys::FILE, -1, c_hmm);
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_status(
+                status,
+                "Failed to write binary HMM",
+            ));
+        }
+        let status = libhmmer_sys::p7_oprofile_Write(
+            self.ffp as *mut libhmmer_sys::FILE,
+            self.pfp as *mut libhmmer_sys::FILE,
+            om,
+        );
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_status(
+                status,
+                "Failed to write optimized profile",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for DbFiles {
+    fn drop(&mut self) {
+        unsafe {
+            // Close the output files first
+            for fp in [self.mfp, self.ffp, self.pfp] {
+                if !fp.is_null() {
+                    libc::fclose(fp);
+                }
+            }
+            if !self.nssi.is_null() {
+                libhmmer_sys::esl_newssi_Close(self.nssi);
+            }
+
+            // Then remove them, if we didn't finish. esl_newssi_Write() takes
+            // care of the ssifile.
+            if !self.finished {
+                for file in [&self.mfile, &self.ffile, &self.pfile] {
+                    libc::remove(file.as_ptr());
+                }
+            }
+        }
+    }
+}
+
+fn path_to_cstring(path: String) -> Result<CString, HmmerError> {
+    CString::new(path).map_err(|_| {
+        HmmerError::InvalidArgument("HMM file path must not contain null bytes".to_string())
+    })
+}
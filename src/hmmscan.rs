@@ -0,0 +1,429 @@
+use log::*;
+use std::ffi::CString;
+use std::marker::PhantomData;
+
+use crate::hmmsearch::{optimized_profile, target_length};
+use crate::{libhmmer_sys_extras, EaselSequence, Hmm, HmmerError, HmmsearchResult, PipelineConfig};
+
+/// A pipeline for scanning query sequences against a collection of HMMs, as
+/// hmmscan does. The HMMs are converted to optimized profiles once, when the
+/// pipeline is created, so that many sequences can be scanned cheaply.
+///
+/// In scan mode the hits are models rather than sequences: each hit's name and
+/// accession are those of the HMM, and E-values are calculated with Z set to
+/// the number of models scanned.
+///
+/// A pipeline can also be opened over a database written by
+/// [`Hmm::press_hmms_to_path`], in which case the profiles are read from disk
+/// for each scan, as hmmscan does.
+pub struct HmmscanPipeline {
+    pli: *mut libhmmer_sys::P7_PIPELINE,
+    bg: *mut libhmmer_sys::P7_BG,
+    abc: *mut libhmmer_sys::ESL_ALPHABET,
+    targets: ScanTargets,
+}
+
+enum ScanTargets {
+    /// Optimized profiles held in memory.
+    Profiles(Vec<*mut libhmmer_sys::P7_OPROFILE>),
+    /// A pressed database, reopened for each scan.
+    Pressed { hmmfile: CString, nmodels: usize },
+}
+
+impl HmmscanPipeline {
+    /// Create a scan pipeline over the given HMMs, which must all share the
+    /// same alphabet.
+    pub fn new(hmms: &[Hmm]) -> Result<HmmscanPipeline, HmmerError> {
+        let first = hmms.first().ok_or_else(|| {
+            HmmerError::InvalidArgument("At least one HMM is required to scan".to_string())
+        })?;
+        let abc_type = unsafe { (*first.c_alphabet()).type_ };
+        for hmm in hmms {
+            if unsafe { (*hmm.c_alphabet()).type_ } != abc_type {
+                return Err(HmmerError::Incompatible(format!(
+                    "HMM {} has a different alphabet to HMM {}",
+                    hmm.name(),
+                    first.name()
+                )));
+            }
+        }
+
+        // The pipeline may outlive the HMMs, so it has an alphabet of its own
+        let abc = unsafe { libhmmer_sys::esl_alphabet_Create(abc_type) };
+        if abc.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
+        let mut pipeline =
+            HmmscanPipeline::create(abc, ScanTargets::Profiles(Vec::with_capacity(hmms.len())))?;
+        if let ScanTargets::Profiles(oms) = &mut pipeline.targets {
+            for hmm in hmms {
+                oms.push(optimized_profile(hmm, pipeline.bg)?);
+            }
+            debug!("Converted {} HMMs to optimized profiles", oms.len());
+        }
+
+        Ok(pipeline)
+    }
+
+    /// Open a scan pipeline over a pressed HMM database. `path` is the path
+    /// given to [`Hmm::press_hmms_to_path`] (or `hmmpress`), i.e. without
+    /// the `.h3m` suffix; the ASCII HMM file itself need not exist.
+    ///
+    /// As in hmmscan, only the MSV filter part of each profile is read up
+    /// front, and the rest is only read for models that pass the filter.
+    pub fn from_pressed_path(path: &std::path::Path) -> Result<HmmscanPipeline, HmmerError> {
+        let hmmfile = CString::new(path.to_string_lossy().as_bytes()).map_err(|_| {
+            HmmerError::InvalidArgument("HMM file path must not contain null bytes".to_string())
+        })?;
+        let hfp = open_pressed(&hmmfile, path)?;
+
+        // hstatus = p7_oprofile_ReadMSV(hfp, &abc, &om);
+        // if      (hstatus == eslEFORMAT)   p7_Fail("bad format, binary auxfiles, %s:\n%s",     cfg->hmmfile, hfp->errbuf);
+        // else if (hstatus == eslEINCOMPAT) p7_Fail("HMM file %s contains different alphabets", cfg->hmmfile);
+        // else if (hstatus != eslOK)        p7_Fail("Unexpected error in reading HMMs from %s",   cfg->hmmfile);
+        // p7_hmmfile_Close(hfp);
+        let mut abc: *mut libhmmer_sys::ESL_ALPHABET = std::ptr::null_mut();
+        let mut om: *mut libhmmer_sys::P7_OPROFILE = std::ptr::null_mut();
+        let (status, nmodels) = unsafe {
+            let status = libhmmer_sys::p7_oprofile_ReadMSV(hfp, &mut abc, &mut om);
+            libhmmer_sys::p7_oprofile_Destroy(om);
+            (status, (*(*hfp).ssi).nprimary as usize)
+        };
+        let read_result = if status != libhmmer_sys::eslOK as i32 {
+            Err(unsafe { pressed_read_error(hfp, status, path) })
+        } else {
+            Ok(())
+        };
+        unsafe { libhmmer_sys::p7_hmmfile_Close(hfp) };
+        if let Err(e) = read_result {
+            unsafe { libhmmer_sys::esl_alphabet_Destroy(abc) };
+            return Err(e);
+        }
+        debug!("Opened pressed HMM database with {nmodels} models");
+
+        HmmscanPipeline::create(abc, ScanTargets::Pressed { hmmfile, nmodels })
+    }
+
+    /// Create the background model and pipeline shared by both target types.
+    /// The pipeline takes over `abc`, which is freed on error.
+    fn create(
+        abc: *mut libhmmer_sys::ESL_ALPHABET,
+        targets: ScanTargets,
+    ) -> Result<HmmscanPipeline, HmmerError> {
+        // bg = p7_bg_Create(abc);
+        let bg = unsafe { libhmmer_sys::p7_bg_Create(abc) };
+        if bg.is_null() {
+            unsafe { libhmmer_sys::esl_alphabet_Destroy(abc) };
+            return Err(HmmerError::OutOfMemory);
+        }
+        debug!("Background model created successfully");
+
+        // pli = p7_pipeline_Create(go, 100, 100, FALSE, p7_SCAN_MODELS); /* M_hint = 100, L_hint = 100 are just dummies for now */
+        let pli = unsafe {
+            libhmmer_sys::p7_pipeline_Create(
+                std::ptr::null_mut(),
+                100,
+                100,
+                0,
+                libhmmer_sys_extras::p7_SCAN_MODELS as u32,
+            )
+        };
+        if pli.is_null() {
+            unsafe {
+                libhmmer_sys::p7_bg_Destroy(bg);
+                libhmmer_sys::esl_alphabet_Destroy(abc);
+            }
+            return Err(HmmerError::OutOfMemory);
+        }
+        debug!("Scan pipeline created successfully");
+
+        Ok(HmmscanPipeline {
+            pli,
+            bg,
+            abc,
+            targets,
+        })
+    }
+
+    /// Set the per-sequence E-value threshold (equivalent to hmmscan -E).
+    /// Default: 10.0
+    pub fn with_seq_evalue(self, e: f64) -> Self {
+        unsafe {
+            (*self.pli).E = e;
+            (*self.pli).use_bit_cutoffs = 0; // FALSE
+            (*self.pli).by_E = 1; // TRUE
+        }
+        self
+    }
+
+    /// Set the per-domain E-value threshold (equivalent to hmmscan --domE).
+    /// Default: 10.0
+    pub fn with_dom_evalue(self, e: f64) -> Self {
+        unsafe {
+            (*self.pli).domE = e;
+            (*self.pli).use_bit_cutoffs = 0; // FALSE
+            (*self.pli).dom_by_E = 1; // TRUE
+        }
+        self
+    }
+
+    /// Set the per-sequence bitscore threshold (equivalent to hmmscan -T).
+    pub fn with_seq_bitscore(self, t: f64) -> Self {
+        unsafe {
+            (*self.pli).T = t;
+            (*self.pli).use_bit_cutoffs = 0; // FALSE
+            (*self.pli).by_E = 0; // FALSE — threshold by score
+        }
+        self
+    }
+
+    /// Set the per-domain bitscore threshold (equivalent to hmmscan --domT).
+    pub fn with_dom_bitscore(self, t: f64) -> Self {
+        unsafe {
+            (*self.pli).domT = t;
+            (*self.pli).use_bit_cutoffs = 0; // FALSE
+            (*self.pli).dom_by_E = 0; // FALSE — threshold by score
+        }
+        self
+    }
+
+    /// Configure the acceleration filters, score corrections and domain
+    /// definition of the pipeline.
+    pub fn with_config(self, config: &PipelineConfig) -> Self {
+        unsafe { config.apply(self.pli) };
+        self
+    }
+
+    /// Number of HMMs in this pipeline.
+    pub fn nmodels(&self) -> usize {
+        match &self.targets {
+            ScanTargets::Profiles(oms) => oms.len(),
+            ScanTargets::Pressed { nmodels, .. } => *nmodels,
+        }
+    }
+
+    /// Scan a single query sequence against every HMM in the pipeline.
+    /// Can be called multiple times, as a fresh top-hits list is created for
+    /// each call.
+    ///
+    /// The returned result refers to this pipeline for its E-values, so it
+    /// borrows the pipeline until it is dropped.
+    pub fn scan_sequence(
+        &mut self,
+        seq: &EaselSequence,
+    ) -> Result<HmmsearchResult<'_>, HmmerError> {
+        unsafe {
+            if (*(*seq.c_sq).abc).type_ != (*(*self.bg).abc).type_ {
+                return Err(HmmerError::Incompatible(
+                    "Query sequence alphabet does not match the HMMs".to_string(),
+                ));
+            }
+        }
+        let n = target_length(seq.c_sq)?;
+
+        let th = unsafe { libhmmer_sys::p7_tophits_Create() };
+        if th.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
+        let result = HmmsearchResult {
+            c_th: th,
+            c_pli: self.pli,
+            owns_pli: false,
+            pipeline: PhantomData,
+        };
+
+        unsafe {
+            // The accounting counters accumulate in the pipeline, and in scan
+            // mode Z is taken from nmodels, so start each query afresh.
+            self.reset_accounting();
+
+            // p7_pli_NewSeq(info->pli, qsq);
+            let status = libhmmer_sys::p7_pli_NewSeq(self.pli, seq.c_sq);
+            if status != libhmmer_sys::eslOK as i32 {
+                return Err(HmmerError::from_status(status, "p7_pli_NewSeq failed"));
+            }
+
+            match &self.targets {
+                ScanTargets::Profiles(oms) => {
+                    for &om in oms {
+                        self.scan_model(om, seq, n, th)?;
+                    }
+                }
+                ScanTargets::Pressed { hmmfile, .. } => {
+                    self.scan_pressed(hmmfile, seq, n, th)?;
+                }
+            }
+
+            // p7_tophits_SortBySortkey(info->th);
+            // p7_tophits_Threshold(info->th, info->pli);
+            libhmmer_sys::p7_tophits_SortBySortkey(th);
+            libhmmer_sys::p7_tophits_Threshold(th, self.pli);
+        }
+        debug!(
+            "Scanned sequence against {} models, {} hits reported",
+            self.nmodels(),
+            result.nreported()
+        );
+
+        Ok(result)
+    }
+
+    /// Run the pipeline for one model against the query, adding any hit to
+    /// `th`.
+    unsafe fn scan_model(
+        &self,
+        om: *mut libhmmer_sys::P7_OPROFILE,
+        seq: &EaselSequence,
+        n: i32,
+        th: *mut libhmmer_sys::P7_TOPHITS,
+    ) -> Result<(), HmmerError> {
+        // p7_pli_NewModel(info->pli, om, info->bg);
+        // p7_bg_SetLength(info->bg, info->qsq->n);
+        // p7_oprofile_ReconfigLength(om, info->qsq->n);
+        let status = libhmmer_sys::p7_pli_NewModel(self.pli, om, self.bg);
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_errbuf(status, (*self.pli).errbuf.as_ptr()));
+        }
+        libhmmer_sys::p7_bg_SetLength(self.bg, n);
+        libhmmer_sys::p7_oprofile_ReconfigLength(om, n);
+
+        // p7_Pipeline(info->pli, om, info->bg, info->qsq, NULL, info->th);
+        let sstatus =
+            libhmmer_sys::p7_Pipeline(self.pli, om, self.bg, seq.c_sq, std::ptr::null_mut(), th);
+        if sstatus != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_errbuf(
+                sstatus,
+                (*self.pli).errbuf.as_ptr(),
+            ));
+        }
+
+        // p7_pipeline_Reuse(info->pli);
+        libhmmer_sys::p7_pipeline_Reuse(self.pli);
+        Ok(())
+    }
+
+    /// Scan the query against each model of a pressed database. Mirrors
+    /// serial_loop() in hmmscan.c.
+    unsafe fn scan_pressed(
+        &self,
+        hmmfile: &CString,
+        seq: &EaselSequence,
+        n: i32,
+        th: *mut libhmmer_sys::P7_TOPHITS,
+    ) -> Result<(), HmmerError> {
+        let path = std::path::PathBuf::from(hmmfile.to_string_lossy().into_owned());
+        let hfp = open_pressed(hmmfile, &path)?;
+        // info[i].pli->hfp = hfp;  /* for two-stage input, pipeline needs <hfp> */
+        (*self.pli).hfp = hfp;
+
+        // while ((status = p7_oprofile_ReadMSV(hfp, &abc, &om)) == eslOK)
+        let mut abc = self.abc;
+        let mut om: *mut libhmmer_sys::P7_OPROFILE = std::ptr::null_mut();
+        let mut result = Ok(());
+        let mut status = libhmmer_sys::p7_oprofile_ReadMSV(hfp, &mut abc, &mut om);
+        while status == libhmmer_sys::eslOK as i32 {
+            result = self.scan_model(om, seq, n, th);
+            // p7_oprofile_Destroy(om);
+            libhmmer_sys::p7_oprofile_Destroy(om);
+            if result.is_err() {
+                break;
+            }
+            status = libhmmer_sys::p7_oprofile_ReadMSV(hfp, &mut abc, &mut om);
+        }
+        if result.is_ok() && status != libhmmer_sys::eslEOF as i32 {
+            result = Err(pressed_read_error(hfp, status, &path));
+        }
+
+        (*self.pli).hfp = std::ptr::null_mut();
+        libhmmer_sys::p7_hmmfile_Close(hfp);
+        result
+    }
+
+    unsafe fn reset_accounting(&mut self) {
+        let pli = &mut *self.pli;
+        pli.nmodels = 0;
+        pli.nseqs = 0;
+        pli.nres = 0;
+        pli.nnodes = 0;
+        pli.n_past_msv = 0;
+        pli.n_past_bias = 0;
+        pli.n_past_vit = 0;
+        pli.n_past_fwd = 0;
+        pli.n_output = 0;
+        pli.pos_past_msv = 0;
+        pli.pos_past_bias = 0;
+        pli.pos_past_vit = 0;
+        pli.pos_past_fwd = 0;
+        pli.pos_output = 0;
+    }
+}
+
+impl Drop for HmmscanPipeline {
+    fn drop(&mut self) {
+        unsafe {
+            if let ScanTargets::Profiles(oms) = &self.targets {
+                for &om in oms {
+                    libhmmer_sys::p7_oprofile_Destroy(om);
+                }
+            }
+            libhmmer_sys::p7_pipeline_Destroy(self.pli);
+            libhmmer_sys::p7_bg_Destroy(self.bg);
+            // The background model points at the alphabet, so free it last.
+            libhmmer_sys::esl_alphabet_Destroy(self.abc);
+        }
+    }
+}
+
+/// Open a pressed HMM database, failing if the binary files are absent.
+fn open_pressed(
+    hmmfile: &CString,
+    path: &std::path::Path,
+) -> Result<*mut libhmmer_sys::P7_HMMFILE, HmmerError> {
+    let mut errbuf = vec![0 as libc::c_char; libhmmer_sys::eslERRBUFSIZE as usize];
+
+    // status = p7_hmmfile_OpenE(cfg->hmmfile, p7_HMMDBENV, &hfp, errbuf);
+    let mut hfp: *mut libhmmer_sys::P7_HMMFILE = std::ptr::null_mut();
+    let status = unsafe {
+        libhmmer_sys::p7_hmmfile_OpenE(
+            hmmfile.as_ptr(),
+            std::ptr::null_mut(),
+            &mut hfp,
+            errbuf.as_mut_ptr(),
+        )
+    };
+    if status != libhmmer_sys::eslOK as i32 {
+        return Err(HmmerError::HmmFile {
+            path: path.to_string_lossy().into_owned(),
+            model_index: None,
+            line: None,
+            source: Box::new(unsafe { HmmerError::from_errbuf(status, errbuf.as_ptr()) }),
+        });
+    }
+
+    // if (! hfp->is_pressed)           p7_Fail("Failed to open binary auxfiles for %s: use hmmpress first\n",             hfp->fname);
+    if unsafe { (*hfp).is_pressed } == 0 {
+        unsafe { libhmmer_sys::p7_hmmfile_Close(hfp) };
+        return Err(HmmerError::NotFound(format!(
+            "Failed to open binary auxfiles for {}: press the HMMs first",
+            path.display()
+        )));
+    }
+    Ok(hfp)
+}
+
+/// Build the error for a failed p7_oprofile_ReadMSV() from a pressed database.
+///
+/// # Safety
+/// `hfp` must be a valid, open HMM file.
+unsafe fn pressed_read_error(
+    hfp: *mut libhmmer_sys::P7_HMMFILE,
+    status: i32,
+    path: &std::path::Path,
+) -> HmmerError {
+    HmmerError::HmmFile {
+        path: path.to_string_lossy().into_owned(),
+        model_index: None,
+        line: None,
+        source: Box::new(HmmerError::from_errbuf(status, (*hfp).errbuf.as_ptr())),
+    }
+}
@@ -1,43 +1,38 @@
 use log::*;
 use std::ffi::{CStr, CString};
+use std::marker::PhantomData;
 
-use crate::{hmm::*, libhmmer_sys_extras, EaselSequence};
+use crate::{hmm::*, libhmmer_sys_extras, EaselSequence, HmmerError};
 
 pub struct HmmerPipeline {
     info: HmmsearchWorkerInfo,
 }
 
 impl HmmerPipeline {
-    pub fn new(hmm: &Hmm) -> HmmerPipeline {
-        // P7_PROFILE      *gm      = NULL;
-        // P7_OPROFILE     *om      = NULL;       /* optimized query profile                  */
-        #[allow(unused_assignments)]
-        let mut gm: *mut libhmmer_sys::P7_PROFILE = std::ptr::null_mut();
-        #[allow(unused_assignments)]
-        let mut om: *mut libhmmer_sys::P7_OPROFILE = std::ptr::null_mut();
-        // int              textw    = 0;
-
+    pub fn new(hmm: &Hmm) -> Result<HmmerPipeline, HmmerError> {
         // WORKER_INFO     *info     = NULL;
-        let bg = unsafe { libhmmer_sys::p7_bg_Create(hmm.c_alphabet()) };
+        // The pipeline may outlive the HMM, so it has an alphabet of its own
+        let abc = unsafe { libhmmer_sys::esl_alphabet_Create((*hmm.c_alphabet()).type_) };
+        if abc.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
+        let bg = unsafe { libhmmer_sys::p7_bg_Create(abc) };
+        if bg.is_null() {
+            unsafe { libhmmer_sys::esl_alphabet_Destroy(abc) };
+            return Err(HmmerError::OutOfMemory);
+        }
         debug!("Background model created successfully");
 
-        //   /* Convert to an optimized model */
-        // gm = p7_profile_Create (hmm->M, abc);
-        // om = p7_oprofile_Create(hmm->M, abc);
-        // p7_ProfileConfig(hmm, info->bg, gm, 100, p7_LOCAL); /* 100 is a dummy length for now; and MSVFilter requires local mode */
-        // p7_oprofile_Convert(gm, om);                  /* <om> is now p7_LOCAL, multihit */
-        let abc = hmm.c_alphabet();
-        gm = unsafe { libhmmer_sys::p7_profile_Create(hmm.length() as i32, abc) };
-        debug!("Profile created successfully");
-        om = unsafe { libhmmer_sys::p7_oprofile_Create(hmm.length() as i32, abc) };
-        debug!("Optimized profile created successfully");
-
-        unsafe {
-            libhmmer_sys::p7_ProfileConfig(hmm.c_hmm, bg, gm, 100, libhmmer_sys_extras::p7_LOCAL);
-            debug!("Profile configured successfully");
-            libhmmer_sys::p7_oprofile_Convert(gm, om);
-            debug!("Optimized profile converted successfully");
-        }
+        let om = match optimized_profile(hmm, bg) {
+            Ok(om) => om,
+            Err(e) => {
+                unsafe {
+                    libhmmer_sys::p7_bg_Destroy(bg);
+                    libhmmer_sys::esl_alphabet_Destroy(abc);
+                }
+                return Err(e);
+            }
+        };
 
         // /* Create processing pipeline and hit list */
         // info[i].th  = p7_tophits_Create();
@@ -57,18 +52,34 @@ impl HmmerPipeline {
                 libhmmer_sys_extras::p7_SEARCH_SEQS as u32,
             )
         };
+        if th.is_null() || pli.is_null() {
+            unsafe {
+                libhmmer_sys::p7_tophits_Destroy(th);
+                libhmmer_sys::p7_pipeline_Destroy(pli);
+                libhmmer_sys::p7_oprofile_Destroy(om);
+                libhmmer_sys::p7_bg_Destroy(bg);
+                libhmmer_sys::esl_alphabet_Destroy(abc);
+            }
+            return Err(HmmerError::OutOfMemory);
+        }
         debug!("Pipeline created successfully");
         unsafe {
             let status = libhmmer_sys::p7_pli_NewModel(pli, om, bg);
-            if status == libhmmer_sys::eslEINVAL as i32 {
-                panic!(); // TODO: Better msg
+            if status != libhmmer_sys::eslOK as i32 {
+                let err = HmmerError::from_errbuf(status, (*pli).errbuf.as_ptr());
+                libhmmer_sys::p7_tophits_Destroy(th);
+                libhmmer_sys::p7_pipeline_Destroy(pli);
+                libhmmer_sys::p7_oprofile_Destroy(om);
+                libhmmer_sys::p7_bg_Destroy(bg);
+                libhmmer_sys::esl_alphabet_Destroy(abc);
+                return Err(err);
             }
         }
         debug!("Pipeline new model created successfully");
 
         let info = HmmsearchWorkerInfo { th, om, pli, bg };
 
-        HmmerPipeline { info }
+        Ok(HmmerPipeline { info })
     }
 
     /// Access the raw pipeline pointer (for setting thresholds in-place).
@@ -82,7 +93,7 @@ impl HmmerPipeline {
         unsafe {
             (*self.info.pli).E = e;
             (*self.info.pli).use_bit_cutoffs = 0; // FALSE
-            (*self.info.pli).inc_by_E = 1; // TRUE
+            (*self.info.pli).by_E = 1; // TRUE
         }
         self
     }
@@ -93,7 +104,7 @@ impl HmmerPipeline {
         unsafe {
             (*self.info.pli).domE = e;
             (*self.info.pli).use_bit_cutoffs = 0; // FALSE
-            (*self.info.pli).inc_by_E = 1; // TRUE
+            (*self.info.pli).dom_by_E = 1; // TRUE
         }
         self
     }
@@ -103,7 +114,7 @@ impl HmmerPipeline {
         unsafe {
             (*self.info.pli).T = t;
             (*self.info.pli).use_bit_cutoffs = 0; // FALSE
-            (*self.info.pli).inc_by_E = 0; // FALSE — threshold by score
+            (*self.info.pli).by_E = 0; // FALSE — threshold by score
         }
         self
     }
@@ -113,19 +124,184 @@ impl HmmerPipeline {
         unsafe {
             (*self.info.pli).domT = t;
             (*self.info.pli).use_bit_cutoffs = 0; // FALSE
+            (*self.info.pli).dom_by_E = 0; // FALSE — threshold by score
+        }
+        self
+    }
+
+    /// Set the per-sequence E-value inclusion threshold (equivalent to
+    /// hmmsearch --incE). Default: 0.01
+    pub fn with_seq_inclusion_evalue(self, e: f64) -> Self {
+        unsafe {
+            (*self.info.pli).incE = e;
+            (*self.info.pli).use_bit_cutoffs = 0; // FALSE
+            (*self.info.pli).inc_by_E = 1; // TRUE
+        }
+        self
+    }
+
+    /// Set the per-domain E-value inclusion threshold (equivalent to
+    /// hmmsearch --incdomE). Default: 0.01
+    pub fn with_dom_inclusion_evalue(self, e: f64) -> Self {
+        unsafe {
+            (*self.info.pli).incdomE = e;
+            (*self.info.pli).use_bit_cutoffs = 0; // FALSE
+            (*self.info.pli).incdom_by_E = 1; // TRUE
+        }
+        self
+    }
+
+    /// Set the per-sequence bitscore inclusion threshold (equivalent to
+    /// hmmsearch --incT).
+    pub fn with_seq_inclusion_bitscore(self, t: f64) -> Self {
+        unsafe {
+            (*self.info.pli).incT = t;
+            (*self.info.pli).use_bit_cutoffs = 0; // FALSE
             (*self.info.pli).inc_by_E = 0; // FALSE — threshold by score
         }
         self
     }
 
+    /// Set the per-domain bitscore inclusion threshold (equivalent to
+    /// hmmsearch --incdomT).
+    pub fn with_dom_inclusion_bitscore(self, t: f64) -> Self {
+        unsafe {
+            (*self.info.pli).incdomT = t;
+            (*self.info.pli).use_bit_cutoffs = 0; // FALSE
+            (*self.info.pli).incdom_by_E = 0; // FALSE — threshold by score
+        }
+        self
+    }
+
+    /// Configure the acceleration filters, score corrections and domain
+    /// definition of the pipeline.
+    pub fn with_config(self, config: &PipelineConfig) -> Self {
+        unsafe { config.apply(self.info.pli) };
+        self
+    }
+
+    /// Fix the effective database size used to calculate per-sequence and
+    /// per-domain independent E-values (equivalent to hmmsearch -Z). By
+    /// default this is the number of target sequences searched, so fixing
+    /// it makes E-values comparable between searches of different shards
+    /// of a database.
+    pub fn with_z(self, z: f64) -> Self {
+        unsafe {
+            (*self.info.pli).Z = z;
+            (*self.info.pli).Z_setby = libhmmer_sys_extras::p7_ZSETBY_OPTION;
+        }
+        self
+    }
+
+    /// Fix the effective database size used to calculate domain conditional
+    /// E-values (equivalent to hmmsearch --domZ). By default this is the
+    /// number of sequences that were reported.
+    pub fn with_dom_z(self, dom_z: f64) -> Self {
+        unsafe {
+            (*self.info.pli).domZ = dom_z;
+            (*self.info.pli).domZ_setby = libhmmer_sys_extras::p7_ZSETBY_OPTION;
+        }
+        self
+    }
+
+    /// Use the model's gathering (GA) bit score cutoffs as both reporting
+    /// and inclusion thresholds, for sequences and domains (equivalent to
+    /// hmmsearch --cut_ga). Returns an error if the model has no GA cutoffs.
+    pub fn with_gathering_cutoffs(self) -> Result<Self, HmmerError> {
+        self.with_bit_cutoffs(libhmmer_sys_extras::p7H_GA)
+    }
+
+    /// Use the model's trusted (TC) bit score cutoffs as both reporting and
+    /// inclusion thresholds, for sequences and domains (equivalent to
+    /// hmmsearch --cut_tc). Returns an error if the model has no TC cutoffs.
+    pub fn with_trusted_cutoffs(self) -> Result<Self, HmmerError> {
+        self.with_bit_cutoffs(libhmmer_sys_extras::p7H_TC)
+    }
+
+    /// Use the model's noise (NC) bit score cutoffs as both reporting and
+    /// inclusion thresholds, for sequences and domains (equivalent to
+    /// hmmsearch --cut_nc). Returns an error if the model has no NC cutoffs.
+    pub fn with_noise_cutoffs(self) -> Result<Self, HmmerError> {
+        self.with_bit_cutoffs(libhmmer_sys_extras::p7H_NC)
+    }
+
+    fn with_bit_cutoffs(self, cutoffs: i32) -> Result<Self, HmmerError> {
+        let pli = self.info.pli;
+        unsafe {
+            // pli->by_E     = pli->dom_by_E    = FALSE;
+            // pli->incT     = pli->incdomT     = 0.0;
+            // pli->inc_by_E = pli->incdom_by_E = FALSE;
+            // pli->use_bit_cutoffs = p7H_GA;
+            (*pli).by_E = 0;
+            (*pli).dom_by_E = 0;
+            (*pli).incT = 0.0;
+            (*pli).incdomT = 0.0;
+            (*pli).inc_by_E = 0;
+            (*pli).incdom_by_E = 0;
+            (*pli).use_bit_cutoffs = cutoffs;
+
+            // The model was already given to the pipeline in new(), so its
+            // cutoffs have to be applied here, as p7_pli_NewModel() would.
+            let status = libhmmer_sys::p7_pli_NewModelThresholds(pli, self.info.om);
+            if status != libhmmer_sys::eslOK as i32 {
+                return Err(HmmerError::from_errbuf(status, (*pli).errbuf.as_ptr()));
+            }
+        }
+        Ok(self)
+    }
+
     /// Run the pipeline over a slice of sequences.
     /// Returns a result object with accumulated hits. Can be called
     /// multiple times (e.g. for different HMMs over the same sequences),
     /// as a fresh top-hits list is created for each call.
-    pub fn search_sequences(&mut self, sequences: &[crate::EaselSequence]) -> HmmsearchResult {
+    pub fn search_sequences(
+        &mut self,
+        sequences: &[crate::EaselSequence],
+    ) -> Result<HmmsearchResult<'_>, HmmerError> {
         // Create a fresh tophits list for this search
         let th = unsafe { libhmmer_sys::p7_tophits_Create() };
+        if th.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
 
+        // Wrap the tophits straight away so that they are freed on error.
+        let result = HmmsearchResult {
+            c_th: th,
+            c_pli: self.info.pli,
+            owns_pli: false,
+            pipeline: PhantomData,
+        };
+        self.search_into(th, sequences)?;
+        Ok(result)
+    }
+
+    /// As search_sequences(), but the result takes over the pipeline, so
+    /// that its hits can be kept after this pipeline is gone.
+    pub(crate) fn into_search_result(
+        mut self,
+        sequences: &[crate::EaselSequence],
+    ) -> Result<HmmsearchResult<'static>, HmmerError> {
+        let th = unsafe { libhmmer_sys::p7_tophits_Create() };
+        if th.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
+
+        let result = HmmsearchResult {
+            c_th: th,
+            c_pli: self.info.pli,
+            owns_pli: true,
+            pipeline: PhantomData,
+        };
+        self.search_into(th, sequences)?;
+        Ok(result)
+    }
+
+    /// Run each sequence through the pipeline, adding its hits to `th`.
+    fn search_into(
+        &mut self,
+        th: *mut libhmmer_sys::P7_TOPHITS,
+        sequences: &[crate::EaselSequence],
+    ) -> Result<(), HmmerError> {
         // Reset pipeline state
         unsafe {
             libhmmer_sys::p7_pipeline_Reuse(self.info.pli);
@@ -133,18 +309,14 @@ impl HmmerPipeline {
 
         for seq in sequences {
             let info = &mut self.info;
+            let n = target_length(seq.c_sq)?;
             unsafe {
-                if libhmmer_sys::p7_pli_NewSeq(info.pli, seq.c_sq) != libhmmer_sys::eslOK as i32 {
-                    panic!("p7_pli_NewSeq failed");
+                let status = libhmmer_sys::p7_pli_NewSeq(info.pli, seq.c_sq);
+                if status != libhmmer_sys::eslOK as i32 {
+                    return Err(HmmerError::from_status(status, "p7_pli_NewSeq failed"));
                 }
-                libhmmer_sys::p7_bg_SetLength(
-                    info.bg,
-                    (*seq.c_sq).n.try_into().expect("i64 -> i32 failed"),
-                );
-                libhmmer_sys::p7_oprofile_ReconfigLength(
-                    info.om,
-                    (*seq.c_sq).n.try_into().expect("i64 -> i32 failed"),
-                );
+                libhmmer_sys::p7_bg_SetLength(info.bg, n);
+                libhmmer_sys::p7_oprofile_ReconfigLength(info.om, n);
 
                 let sstatus = libhmmer_sys::p7_Pipeline(
                     info.pli,
@@ -155,7 +327,10 @@ impl HmmerPipeline {
                     th,
                 );
                 if sstatus != libhmmer_sys::eslOK as i32 {
-                    panic!("p7_Pipeline sstatus indicated failure, was {sstatus}");
+                    return Err(HmmerError::from_errbuf(
+                        sstatus,
+                        (*info.pli).errbuf.as_ptr(),
+                    ));
                 }
 
                 libhmmer_sys::p7_pipeline_Reuse(info.pli);
@@ -167,37 +342,51 @@ impl HmmerPipeline {
             libhmmer_sys::p7_tophits_Threshold(th, self.info.pli);
         }
 
-        HmmsearchResult {
-            c_th: th,
-            c_pli: self.info.pli,
-            owns_pli: false,
-        }
+        Ok(())
     }
 
-    pub fn run_hmm_on_file(&mut self, hmm: &Hmm, fasta_path: &std::path::Path) -> HmmsearchResult {
+    pub fn run_hmm_on_file(
+        &mut self,
+        hmm: &Hmm,
+        fasta_path: &std::path::Path,
+    ) -> Result<HmmsearchResult<'_>, HmmerError> {
         debug!("Starting run_hmm_on_file");
         #[allow(unused_mut)]
-        let mut dbfile = Self::open_target_sequences(&fasta_path.to_string_lossy());
+        let mut dbfile = Self::open_target_sequences(&fasta_path.to_string_lossy())?;
         debug!("Target sequences opened successfully");
 
         // TODO: output_header(ofp, go, cfg->hmmfile, cfg->dbfile);
 
         //       esl_sqfile_SetDigital(dbfp, abc); //ReadBlock requires knowledge of the alphabet to decide how best to read blocks
-        unsafe {
-            libhmmer_sys::esl_sqfile_SetDigital(dbfile, hmm.c_alphabet());
+        let status = unsafe { libhmmer_sys::esl_sqfile_SetDigital(dbfile, hmm.c_alphabet()) };
+        if status != libhmmer_sys::eslOK as i32 {
+            unsafe { libhmmer_sys::esl_sqfile_Close(dbfile) };
+            return Err(HmmerError::from_status(
+                status,
+                format!(
+                    "Failed to set digital mode on sequence file {}",
+                    fasta_path.to_string_lossy()
+                ),
+            ));
         }
         debug!("Target sequences set to digital successfully");
 
         // if (fprintf(ofp, "Query:       %s  [M=%d]\n", hmm->name, hmm->M)  < 0) ESL_EXCEPTION_SYS(eslEWRITE, "write failed");
         // if (hmm->acc)  { if (fprintf(ofp, "Accession:   %s\n", hmm->acc)  < 0) ESL_EXCEPTION_SYS(eslEWRITE, "write failed"); }
         // if (hmm->desc) { if (fprintf(ofp, "Description: %s\n", hmm->desc) < 0) ESL_EXCEPTION_SYS(eslEWRITE, "write failed"); }
-        println!("Query:       {}  [M={}]", hmm.name(), hmm.length());
-        println!("Accession:   {}", hmm.acc());
-        println!("Description: {}", hmm.desc());
+        debug!("Query:       {}  [M={}]", hmm.name(), hmm.length());
+        debug!("Accession:   {}", hmm.acc());
+        debug!("Description: {}", hmm.desc());
 
         // sstatus = serial_loop(info, dbfp, cfg->n_targetseq);
         // TODO: n_targetseq == -1 means no limit. OK for now.
-        let sstatus = self.serial_loop_over_esl_sqio(dbfile, -1);
+        let sstatus = match self.serial_loop_over_esl_sqio(dbfile, -1) {
+            Ok(sstatus) => sstatus,
+            Err(e) => {
+                unsafe { libhmmer_sys::esl_sqfile_Close(dbfile) };
+                return Err(e);
+            }
+        };
 
         // switch(sstatus)
         // {
@@ -216,26 +405,36 @@ impl HmmerPipeline {
         // compile-time warnings.
         const ESL_FORMAT: i32 = libhmmer_sys::eslEFORMAT as i32;
         const ESL_EOF: i32 = libhmmer_sys::eslEOF as i32;
-        match sstatus {
+        let loop_result = match sstatus {
             ESL_FORMAT => {
-                // panic!("Parse failed (sequence file {}):\n{}", fasta_path.to_string_lossy(), unsafe { libhmmer_sys::esl_sqfile_GetErrorBuf(dbfile) });
-                // TODO: Make the above compile
-                panic!(
-                    "Parse failed (sequence file {})",
-                    fasta_path.to_string_lossy()
-                );
+                let errbuf = unsafe {
+                    CStr::from_ptr(libhmmer_sys::esl_sqfile_GetErrorBuf(dbfile))
+                        .to_string_lossy()
+                        .into_owned()
+                };
+                Err(HmmerError::Format(format!(
+                    "Parse failed (sequence file {}):\n{}",
+                    fasta_path.to_string_lossy(),
+                    errbuf
+                )))
             }
             ESL_EOF => {
                 // do nothing
+                Ok(())
             }
-            _ => {
-                panic!(
+            _ => Err(HmmerError::from_status(
+                sstatus,
+                format!(
                     "Unexpected error {} reading sequence file {}",
                     sstatus,
                     fasta_path.to_string_lossy()
-                );
-            }
+                ),
+            )),
+        };
+        unsafe {
+            libhmmer_sys::esl_sqfile_Close(dbfile);
         }
+        loop_result?;
 
         // /* merge the results of the search results */
         // for (i = 1; i < infocnt; ++i)
@@ -272,15 +471,14 @@ impl HmmerPipeline {
 
         // TODO: Destroy, free, etc.
 
-        HmmsearchResult {
-            c_th: self.info.th,
-            c_pli: self.info.pli,
-            owns_pli: false,
-        }
+        self.take_results()
     }
 
-    // TODO: Add a coresponding free() method
-    fn open_target_sequences(fasta_file: &str) -> *mut libhmmer_sys::esl_sqio_s {
+    /// Open a sequence file for reading. The returned handle must be closed
+    /// with esl_sqfile_Close().
+    fn open_target_sequences(
+        fasta_file: &str,
+    ) -> Result<*mut libhmmer_sys::esl_sqio_s, HmmerError> {
         //   int              dbfmt    = eslSQFILE_UNKNOWN; /* format code for sequence database file          */
         let dbfmt = 0; //libhmmer_sys::eslSQFILE_UNKNOWN;
 
@@ -293,34 +491,48 @@ impl HmmerPipeline {
         //   else if (status == eslEFORMAT)   p7_Fail("Sequence file %s is empty or misformatted\n",            cfg->dbfile);
         //   else if (status == eslEINVAL)    p7_Fail("Can't autodetect format of a stdin or .gz seqfile");
         //   else if (status != eslOK)        p7_Fail("Unexpected error %d opening sequence file %s\n", status, cfg->dbfile);
-        let file_pointer = CString::new(fasta_file.as_bytes()).unwrap().into_raw();
+        let file_cstring = CString::new(fasta_file.as_bytes()).map_err(|_| {
+            HmmerError::InvalidArgument(format!(
+                "Sequence file path {fasta_file} must not contain null bytes"
+            ))
+        })?;
         let status = unsafe {
             // Open the file not assuming anything about its format, and let
             // autodetect do its thing. Possibly we should use eslSQFILE_FASTA.
-            libhmmer_sys::esl_sqfile_Open(file_pointer, dbfmt, std::ptr::null(), &mut dbfp)
+            libhmmer_sys::esl_sqfile_Open(file_cstring.as_ptr(), dbfmt, std::ptr::null(), &mut dbfp)
         };
-        println!("Opened fasta file with status {status}");
+        debug!("Opened fasta file with status {status}");
 
         if status == libhmmer_sys::eslENOTFOUND as i32 {
-            panic!("Failed to open sequence file {fasta_file} for reading");
+            return Err(HmmerError::NotFound(format!(
+                "Failed to open sequence file {fasta_file} for reading"
+            )));
         } else if status == libhmmer_sys::eslEFORMAT as i32 {
-            panic!("Sequence file {fasta_file} is empty or misformatted");
+            return Err(HmmerError::Format(format!(
+                "Sequence file {fasta_file} is empty or misformatted"
+            )));
         } else if status == libhmmer_sys::eslEINVAL as i32 {
-            panic!("Can't autodetect format of a stdin or .gz seqfile");
+            return Err(HmmerError::InvalidArgument(
+                "Can't autodetect format of a stdin or .gz seqfile".to_string(),
+            ));
         } else if status != libhmmer_sys::eslOK as i32 {
-            panic!("Unexpected error {status} opening sequence file {fasta_file}");
+            return Err(HmmerError::from_status(
+                status,
+                format!("Unexpected error {status} opening sequence file {fasta_file}"),
+            ));
         }
-        dbfp
+        Ok(dbfp)
     }
 
     /// This method (called serial_loop in C) is not available in libhmmer_sys,
     /// so we have to implement it here. Intended as a direct replacement for
-    /// the C function.
+    /// the C function, except that failures of the pipeline itself are
+    /// returned as errors rather than ignored.
     fn serial_loop_over_esl_sqio(
         &mut self,
         dbfp: *mut libhmmer_sys::esl_sqio_s,
         n_targetseqs: i32,
-    ) -> i32 {
+    ) -> Result<i32, HmmerError> {
         debug!("serial_loop");
 
         //   int              status;                       /* easel return code                               */
@@ -358,20 +570,21 @@ impl HmmerPipeline {
         while (n_targetseqs == -1 || seq_cnt < n_targetseqs)
             && sstatus == libhmmer_sys::eslOK as i32
         {
+            let n = match target_length(dbsq) {
+                Ok(n) => n,
+                Err(e) => {
+                    unsafe { libhmmer_sys::esl_sq_Destroy(dbsq) };
+                    return Err(e);
+                }
+            };
             unsafe {
                 // p7_pli_NewSeq(info->pli, dbsq);
                 libhmmer_sys::p7_pli_NewSeq(info.pli, dbsq);
 
                 // p7_bg_SetLength(info->bg, dbsq->n);
-                libhmmer_sys::p7_bg_SetLength(
-                    info.bg,
-                    (*dbsq).n.try_into().expect("i64 -> i32 failed"),
-                );
+                libhmmer_sys::p7_bg_SetLength(info.bg, n);
                 // p7_oprofile_ReconfigLength(info->om, dbsq->n);
-                libhmmer_sys::p7_oprofile_ReconfigLength(
-                    info.om,
-                    (*dbsq).n.try_into().expect("i64 -> i32 failed"),
-                );
+                libhmmer_sys::p7_oprofile_ReconfigLength(info.om, n);
 
                 // p7_Pipeline(info->pli, info->om, info->bg, dbsq, NULL, info->th);
                 let p7_sstatus = libhmmer_sys::p7_Pipeline(
@@ -383,7 +596,11 @@ impl HmmerPipeline {
                     info.th,
                 );
                 if p7_sstatus != libhmmer_sys::eslOK as i32 {
-                    panic!("p7_Pipeline sstatus indicated failure, was {p7_sstatus}");
+                    libhmmer_sys::esl_sq_Destroy(dbsq);
+                    return Err(HmmerError::from_errbuf(
+                        p7_sstatus,
+                        (*info.pli).errbuf.as_ptr(),
+                    ));
                 }
 
                 // In the C code, this is part of the while loop condition.
@@ -412,41 +629,32 @@ impl HmmerPipeline {
             libhmmer_sys::esl_sq_Destroy(dbsq);
         }
 
-        sstatus
+        Ok(sstatus)
     }
 
-    pub fn query(&mut self, easel_sequence: &EaselSequence) {
+    pub fn query(&mut self, easel_sequence: &EaselSequence) -> Result<(), HmmerError> {
         let info = &mut self.info;
+        let n = target_length(easel_sequence.c_sq)?;
 
         unsafe {
             // p7_pli_NewSeq(info->pli, dbsq);
-            if libhmmer_sys::p7_pli_NewSeq(info.pli, easel_sequence.c_sq)
-                != libhmmer_sys::eslOK as i32
-            {
-                panic!()
+            let status = libhmmer_sys::p7_pli_NewSeq(info.pli, easel_sequence.c_sq);
+            if status != libhmmer_sys::eslOK as i32 {
+                return Err(HmmerError::from_status(status, "p7_pli_NewSeq failed"));
             };
 
             // p7_bg_SetLength(info->bg, dbsq->n);
-            if libhmmer_sys::p7_bg_SetLength(
-                info.bg,
-                (*easel_sequence.c_sq)
-                    .n
-                    .try_into()
-                    .expect("i64 -> i32 failed"),
-            ) != libhmmer_sys::eslOK as i32
-            {
-                panic!()
+            let status = libhmmer_sys::p7_bg_SetLength(info.bg, n);
+            if status != libhmmer_sys::eslOK as i32 {
+                return Err(HmmerError::from_status(status, "p7_bg_SetLength failed"));
             };
             // p7_oprofile_ReconfigLength(info->om, dbsq->n);
-            if libhmmer_sys::p7_oprofile_ReconfigLength(
-                info.om,
-                (*easel_sequence.c_sq)
-                    .n
-                    .try_into()
-                    .expect("i64 -> i32 failed"),
-            ) != libhmmer_sys::eslOK as i32
-            {
-                panic!()
+            let status = libhmmer_sys::p7_oprofile_ReconfigLength(info.om, n);
+            if status != libhmmer_sys::eslOK as i32 {
+                return Err(HmmerError::from_status(
+                    status,
+                    "p7_oprofile_ReconfigLength failed",
+                ));
             };
 
             // p7_Pipeline(info->pli, info->om, info->bg, dbsq, NULL, info->th);
@@ -460,26 +668,186 @@ impl HmmerPipeline {
             );
             debug!("query p7_Pipeline sstatus {}", sstatus);
             if sstatus != libhmmer_sys::eslOK as i32 {
-                panic!("p7_Pipeline sstatus indicated failure, was {sstatus}");
+                return Err(HmmerError::from_errbuf(
+                    sstatus,
+                    (*info.pli).errbuf.as_ptr(),
+                ));
             }
         }
+        Ok(())
     }
 
-    pub fn get_results(&mut self) -> HmmsearchResult {
+    /// The hits accumulated by query() since the last call. Subsequent
+    /// queries start a fresh hit list.
+    pub fn get_results(&mut self) -> Result<HmmsearchResult<'_>, HmmerError> {
         unsafe {
             debug!("Running p7_tophits_SortBySortkey");
             libhmmer_sys::p7_tophits_SortBySortkey(self.info.th);
             debug!("Running p7_tophits_Threshold");
             libhmmer_sys::p7_tophits_Threshold(self.info.th, self.info.pli);
         }
-        HmmsearchResult {
-            c_th: self.info.th,
+        self.take_results()
+    }
+
+    /// Hand the current top hits list over to a result, which frees it, and
+    /// replace it with an empty one. Otherwise the next query would add hits
+    /// to (and the next result free again) a list that has been freed.
+    fn take_results(&mut self) -> Result<HmmsearchResult<'_>, HmmerError> {
+        let th = unsafe { libhmmer_sys::p7_tophits_Create() };
+        if th.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
+        Ok(HmmsearchResult {
+            c_th: std::mem::replace(&mut self.info.th, th),
             c_pli: self.info.pli,
             owns_pli: false,
+            pipeline: PhantomData,
+        })
+    }
+}
+
+/// Acceleration filter, score correction and domain definition settings of
+/// a search or scan pipeline. These are fixed at HMMER's defaults unless a
+/// configuration is given with [`HmmerPipeline::with_config`] or
+/// [`crate::HmmscanPipeline::with_config`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PipelineConfig {
+    /// P-value threshold of the MSV filter (--F1). Default: 0.02
+    pub f1: f64,
+    /// P-value threshold of the Viterbi filter (--F2). Default: 1e-3
+    pub f2: f64,
+    /// P-value threshold of the Forward filter (--F3). Default: 1e-5
+    pub f3: f64,
+    /// Turn off all filters, including the bias filter, so that every
+    /// target is scored with Forward/Backward (--max). This overrides the
+    /// filter settings above. Default: false
+    pub max_sensitivity: bool,
+    /// Apply the composition bias filter (turned off by --nobias).
+    /// Default: true
+    pub bias_filter: bool,
+    /// Apply the null2 composition bias score correction (turned off by
+    /// --nonull2). Default: true
+    pub null2: bool,
+    /// Domain definition: minimum posterior probability of being in the
+    /// model that triggers a region around a position. Default: 0.25
+    pub rt1: f32,
+    /// Domain definition: regions are extended while the posterior
+    /// probability of being in the model is at least this. Default: 0.10
+    pub rt2: f32,
+    /// Domain definition: regions with at least this many expected extra
+    /// domains are split by stochastic traceback clustering. Default: 0.20
+    pub rt3: f32,
+    /// Domain definition: number of stochastic traces sampled to split a
+    /// region into domains. Default: 200
+    pub domain_samples: u32,
+}
+
+impl Default for PipelineConfig {
+    // Defaults as set by p7_pipeline_Create() and p7_domaindef_Create()
+    fn default() -> Self {
+        PipelineConfig {
+            f1: 0.02,
+            f2: 1e-3,
+            f3: 1e-5,
+            max_sensitivity: false,
+            bias_filter: true,
+            null2: true,
+            rt1: 0.25,
+            rt2: 0.10,
+            rt3: 0.20,
+            domain_samples: 200,
+        }
+    }
+}
+
+impl PipelineConfig {
+    pub(crate) unsafe fn apply(&self, pli: *mut libhmmer_sys::P7_PIPELINE) {
+        // pli->F1     = ESL_MIN(1.0, esl_opt_GetReal(go, "--F1"));
+        // pli->F2     = ESL_MIN(1.0, esl_opt_GetReal(go, "--F2"));
+        // pli->F3     = ESL_MIN(1.0, esl_opt_GetReal(go, "--F3"));
+        (*pli).F1 = self.f1.min(1.0);
+        (*pli).F2 = self.f2.min(1.0);
+        (*pli).F3 = self.f3.min(1.0);
+        (*pli).do_max = 0;
+        (*pli).do_biasfilter = self.bias_filter as i32;
+        (*pli).do_null2 = self.null2 as i32;
+        if self.max_sensitivity {
+            // pli->do_max        = TRUE;
+            // pli->do_biasfilter = FALSE;
+            // pli->F2 = pli->F3 = 1.0;
+            // pli->F1 = (pli->long_targets ? 0.3 : 1.0);
+            (*pli).do_max = 1;
+            (*pli).do_biasfilter = 0;
+            (*pli).F2 = 1.0;
+            (*pli).F3 = 1.0;
+            (*pli).F1 = if (*pli).long_targets != 0 { 0.3 } else { 1.0 };
         }
+
+        let ddef = (*pli).ddef;
+        (*ddef).rt1 = self.rt1;
+        (*ddef).rt2 = self.rt2;
+        (*ddef).rt3 = self.rt3;
+        (*ddef).nsamples = self.domain_samples as i32;
     }
 }
 
+/// Convert a HMM into an optimized profile, configured against the given
+/// background model. The profile uses the background model's alphabet
+/// rather than the HMM's, so it does not refer to the HMM. The caller owns
+/// the returned P7_OPROFILE.
+pub(crate) fn optimized_profile(
+    hmm: &Hmm,
+    bg: *mut libhmmer_sys::P7_BG,
+) -> Result<*mut libhmmer_sys::P7_OPROFILE, HmmerError> {
+    //   /* Convert to an optimized model */
+    // gm = p7_profile_Create (hmm->M, abc);
+    // om = p7_oprofile_Create(hmm->M, abc);
+    // p7_ProfileConfig(hmm, info->bg, gm, 100, p7_LOCAL); /* 100 is a dummy length for now; and MSVFilter requires local mode */
+    // p7_oprofile_Convert(gm, om);                  /* <om> is now p7_LOCAL, multihit */
+    let abc = unsafe { (*bg).abc };
+    let gm = unsafe { libhmmer_sys::p7_profile_Create(hmm.length() as i32, abc) };
+    debug!("Profile created successfully");
+    let om = unsafe { libhmmer_sys::p7_oprofile_Create(hmm.length() as i32, abc) };
+    debug!("Optimized profile created successfully");
+    if gm.is_null() || om.is_null() {
+        unsafe {
+            libhmmer_sys::p7_profile_Destroy(gm);
+            libhmmer_sys::p7_oprofile_Destroy(om);
+        }
+        return Err(HmmerError::OutOfMemory);
+    }
+
+    unsafe {
+        let status =
+            libhmmer_sys::p7_ProfileConfig(hmm.c_hmm, bg, gm, 100, libhmmer_sys_extras::p7_LOCAL);
+        if status != libhmmer_sys::eslOK as i32 {
+            libhmmer_sys::p7_profile_Destroy(gm);
+            libhmmer_sys::p7_oprofile_Destroy(om);
+            return Err(HmmerError::from_status(status, "p7_ProfileConfig failed"));
+        }
+        debug!("Profile configured successfully");
+        let status = libhmmer_sys::p7_oprofile_Convert(gm, om);
+        libhmmer_sys::p7_profile_Destroy(gm);
+        if status != libhmmer_sys::eslOK as i32 {
+            libhmmer_sys::p7_oprofile_Destroy(om);
+            return Err(HmmerError::from_status(
+                status,
+                "p7_oprofile_Convert failed",
+            ));
+        }
+        debug!("Optimized profile converted successfully");
+    }
+    Ok(om)
+}
+
+/// Length of a target sequence, as the i32 expected by the HMMER C API.
+pub(crate) fn target_length(sq: *const libhmmer_sys::ESL_SQ) -> Result<i32, HmmerError> {
+    unsafe { (*sq).n }.try_into().map_err(|_| {
+        HmmerError::InvalidArgument("Sequence is too long (i64 -> i32 failed)".to_string())
+    })
+}
+
 // typedef struct {
 //       P7_BG            *bg;	         /* null model                              */
 //       P7_PIPELINE      *pli;         /* work pipeline                           */
@@ -493,14 +861,18 @@ pub struct HmmsearchWorkerInfo {
     om: *mut libhmmer_sys::P7_OPROFILE,
 }
 
+/// Hits of a search or scan. The E-values and statistics are read from the
+/// pipeline that produced the result, so it borrows that pipeline until it
+/// is dropped; use [`HmmsearchResult::to_owned`] to keep the hits longer.
 #[derive(Debug)]
-pub struct HmmsearchResult {
+pub struct HmmsearchResult<'a> {
     pub c_th: *mut libhmmer_sys::p7_tophits_s,
     pub c_pli: *mut libhmmer_sys::p7_pipeline_s,
-    owns_pli: bool,
+    pub(crate) owns_pli: bool,
+    pub(crate) pipeline: PhantomData<&'a mut libhmmer_sys::p7_pipeline_s>,
 }
 
-impl Drop for HmmsearchResult {
+impl Drop for HmmsearchResult<'_> {
     fn drop(&mut self) {
         unsafe {
             libhmmer_sys::p7_tophits_Destroy(self.c_th);
@@ -511,36 +883,76 @@ impl Drop for HmmsearchResult {
     }
 }
 
-impl HmmsearchResult {
+impl HmmsearchResult<'_> {
     /// Number of reported hits
     pub fn nreported(&self) -> usize {
-        unsafe {
-            (*self.c_th)
-                .nreported
-                .try_into()
-                .expect("i64 -> usize failed")
-        }
+        unsafe { usize::try_from((*self.c_th).nreported).unwrap_or(0) }
     }
 
-    pub fn hits(&self) -> HmmsearchResultTopHits {
+    /// The effective database size that per-sequence and independent
+    /// domain E-values were calculated with: either the number of targets
+    /// searched, or the value fixed with [`HmmerPipeline::with_z`].
+    pub fn z(&self) -> f64 {
+        unsafe { (*self.c_pli).Z }
+    }
+
+    /// The effective database size that conditional domain E-values were
+    /// calculated with: either the number of reported targets, or the value
+    /// fixed with [`HmmerPipeline::with_dom_z`].
+    pub fn dom_z(&self) -> f64 {
+        unsafe { (*self.c_pli).domZ }
+    }
+
+    pub fn hits(&self) -> HmmsearchResultTopHits<'_> {
         HmmsearchResultTopHits {
             c_th: self.c_th,
             c_pli: self.c_pli,
             current: 0,
             nreported: self.nreported(),
+            result: PhantomData,
+        }
+    }
+
+    /// Numbers of targets searched and passing each stage of the pipeline.
+    pub fn statistics(&self) -> PipelineStatistics {
+        let pli = unsafe { &*self.c_pli };
+        PipelineStatistics {
+            nmodels: pli.nmodels,
+            nseqs: pli.nseqs,
+            nres: pli.nres,
+            nnodes: pli.nnodes,
+            n_past_msv: pli.n_past_msv,
+            n_past_bias: pli.n_past_bias,
+            n_past_vit: pli.n_past_vit,
+            n_past_fwd: pli.n_past_fwd,
+            n_output: pli.n_output,
+        }
+    }
+
+    /// Copy the reported hits, their domains, the E-value search space sizes
+    /// and the pipeline statistics into plain Rust values, which stay valid
+    /// after the result and its pipeline are dropped or reused.
+    pub fn to_owned(&self) -> SearchResult {
+        SearchResult {
+            z: self.z(),
+            dom_z: self.dom_z(),
+            statistics: self.statistics(),
+            hits: self.hits().map(|hit| hit.to_owned()).collect(),
         }
     }
 }
 
-pub struct HmmsearchResultTopHits {
+/// The reported hits of a result, which they borrow.
+pub struct HmmsearchResultTopHits<'a> {
     c_th: *mut libhmmer_sys::p7_tophits_s,
     c_pli: *mut libhmmer_sys::p7_pipeline_s,
     current: usize,
     nreported: usize,
+    result: PhantomData<&'a ()>,
 }
 
-impl Iterator for HmmsearchResultTopHits {
-    type Item = HmmsearchResultTopHit;
+impl<'a> Iterator for HmmsearchResultTopHits<'a> {
+    type Item = HmmsearchResultTopHit<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.current >= self.nreported {
@@ -551,6 +963,7 @@ impl Iterator for HmmsearchResultTopHits {
             c_hit: unsafe { *(*self.c_th).hit.add(self.current) },
             c_pli: self.c_pli,
             current_domain: 0,
+            result: PhantomData,
         };
 
         self.current += 1;
@@ -559,13 +972,14 @@ impl Iterator for HmmsearchResultTopHits {
     }
 }
 
-pub struct HmmsearchResultTopHit {
+pub struct HmmsearchResultTopHit<'a> {
     c_hit: *mut libhmmer_sys::p7_hit_s,
     c_pli: *mut libhmmer_sys::p7_pipeline_s,
     current_domain: usize,
+    result: PhantomData<&'a ()>,
 }
 
-impl HmmsearchResultTopHit {
+impl<'a> HmmsearchResultTopHit<'a> {
     // println!("Name of first hit {}", unsafe {
     //     CStr::from_ptr(first_hit.name).to_string_lossy()
     // });
@@ -577,11 +991,74 @@ impl HmmsearchResultTopHit {
         }
     }
 
+    /// Accession of the hit (empty if unset). In a scan this is the accession
+    /// of the model that was hit.
+    pub fn acc(&self) -> String {
+        unsafe {
+            if (*self.c_hit).acc.is_null() {
+                return String::new();
+            }
+            CStr::from_ptr((*self.c_hit).acc)
+                .to_string_lossy()
+                .into_owned()
+        }
+    }
+
+    /// Description of the hit (empty if unset). In a scan this is the
+    /// description of the model that was hit.
+    pub fn description(&self) -> String {
+        unsafe {
+            if (*self.c_hit).desc.is_null() {
+                return String::new();
+            }
+            CStr::from_ptr((*self.c_hit).desc)
+                .to_string_lossy()
+                .into_owned()
+        }
+    }
+
     // println!("Score of first hit overall {}", first_hit.score);
     pub fn score(&self) -> f32 {
         unsafe { (*self.c_hit).score }
     }
 
+    /// Per-sequence bitscore before the null2 composition bias correction.
+    pub fn pre_score(&self) -> f32 {
+        unsafe { (*self.c_hit).pre_score }
+    }
+
+    /// Sum of the scores of the domains of the hit, which replaces the
+    /// per-sequence score when it is higher.
+    pub fn sum_score(&self) -> f32 {
+        unsafe { (*self.c_hit).sum_score }
+    }
+
+    /// Composition bias correction of the per-sequence score, in bits
+    /// (the "bias" column of --tblout).
+    pub fn bias(&self) -> f32 {
+        unsafe { (*self.c_hit).pre_score - (*self.c_hit).score }
+    }
+
+    /// Natural log of the per-sequence P-value.
+    pub fn ln_pvalue(&self) -> f64 {
+        unsafe { (*self.c_hit).lnP }
+    }
+
+    /// Natural log of the P-value of pre_score().
+    pub fn pre_ln_pvalue(&self) -> f64 {
+        unsafe { (*self.c_hit).pre_lnP }
+    }
+
+    /// Natural log of the P-value of sum_score().
+    pub fn sum_ln_pvalue(&self) -> f64 {
+        unsafe { (*self.c_hit).sum_lnP }
+    }
+
+    /// Per-sequence P-value.
+    pub fn pvalue(&self) -> f64 {
+        self.ln_pvalue().exp()
+    }
+
     /// Per-sequence bitscore (alias for score()).
     pub fn bitscore(&self) -> f32 {
         self.score()
@@ -591,20 +1068,139 @@ impl HmmsearchResultTopHit {
     pub fn evalue(&self) -> f64 {
         unsafe { (*self.c_hit).lnP.exp() * (*self.c_pli).Z }
     }
+
+    /// Whether the hit meets the reporting thresholds. Only reported hits
+    /// are iterated over by [`HmmsearchResult::hits`].
+    pub fn is_reported(&self) -> bool {
+        unsafe { (*self.c_hit).flags & libhmmer_sys_extras::p7_IS_REPORTED != 0 }
+    }
+
+    /// Whether the hit meets the inclusion thresholds, i.e. would be
+    /// included in an output alignment or the next jackhmmer iteration.
+    pub fn is_included(&self) -> bool {
+        unsafe { (*self.c_hit).flags & libhmmer_sys_extras::p7_IS_INCLUDED != 0 }
+    }
+
+    /// Whether the hit was marked as a duplicate of another hit, which
+    /// happens for overlapping hits in long target (nhmmer) searches.
+    pub fn is_duplicate(&self) -> bool {
+        unsafe { (*self.c_hit).flags & libhmmer_sys_extras::p7_IS_DUPLICATE != 0 }
+    }
+
+    /// Expected number of domains, from posterior decoding (the "exp"
+    /// column of --tblout).
+    pub fn nexpected(&self) -> f32 {
+        unsafe { (*self.c_hit).nexpected }
+    }
+
+    /// Number of regions defined by posterior decoding ("reg").
+    pub fn nregions(&self) -> usize {
+        count(unsafe { (*self.c_hit).nregions })
+    }
+
+    /// Number of regions that were split into domains by stochastic
+    /// traceback clustering ("clu").
+    pub fn nclustered(&self) -> usize {
+        count(unsafe { (*self.c_hit).nclustered })
+    }
+
+    /// Number of envelopes that overlapped another envelope ("ov").
+    pub fn noverlaps(&self) -> usize {
+        count(unsafe { (*self.c_hit).noverlaps })
+    }
+
+    /// Number of envelopes defined ("env").
+    pub fn nenvelopes(&self) -> usize {
+        count(unsafe { (*self.c_hit).nenvelopes })
+    }
+
+    /// Number of domains defined ("dom").
+    pub fn ndom(&self) -> usize {
+        count(unsafe { (*self.c_hit).ndom })
+    }
+
+    /// Number of domains that meet the domain reporting thresholds ("rep").
+    pub fn nreported_domains(&self) -> usize {
+        count(unsafe { (*self.c_hit).nreported })
+    }
+
+    /// Number of domains that meet the domain inclusion thresholds ("inc").
+    pub fn nincluded_domains(&self) -> usize {
+        count(unsafe { (*self.c_hit).nincluded })
+    }
+
+    /// Copy the hit and all of its domains into a plain Rust value.
+    pub fn to_owned(&self) -> Hit {
+        let ndom = self.ndom();
+        Hit {
+            name: self.name(),
+            accession: unsafe { optional_string((*self.c_hit).acc) },
+            description: unsafe { optional_string((*self.c_hit).desc) },
+            score: self.score(),
+            pre_score: self.pre_score(),
+            sum_score: self.sum_score(),
+            bias: self.bias(),
+            evalue: self.evalue(),
+            ln_pvalue: self.ln_pvalue(),
+            pre_ln_pvalue: self.pre_ln_pvalue(),
+            sum_ln_pvalue: self.sum_ln_pvalue(),
+            nexpected: self.nexpected(),
+            nregions: self.nregions(),
+            nclustered: self.nclustered(),
+            noverlaps: self.noverlaps(),
+            nenvelopes: self.nenvelopes(),
+            nreported_domains: self.nreported_domains(),
+            nincluded_domains: self.nincluded_domains(),
+            is_reported: self.is_reported(),
+            is_included: self.is_included(),
+            is_duplicate: self.is_duplicate(),
+            best_domain: (ndom > 0).then(|| count(unsafe { (*self.c_hit).best_domain })),
+            domains: (0..ndom)
+                .map(|d| {
+                    HmmsearchResultDomain {
+                        c_dom: unsafe { (*self.c_hit).dcl.add(d) },
+                        c_pli: self.c_pli,
+                        result: PhantomData,
+                    }
+                    .to_owned()
+                })
+                .collect(),
+        }
+    }
+
+    /// The highest scoring domain of the hit, which gives the "best 1
+    /// domain" columns of --tblout. None if the hit has no domains.
+    pub fn best_domain(&self) -> Option<HmmsearchResultDomain<'a>> {
+        unsafe {
+            if (*self.c_hit).ndom == 0 {
+                return None;
+            }
+            Some(HmmsearchResultDomain {
+                c_dom: (*self.c_hit).dcl.add(count((*self.c_hit).best_domain)),
+                c_pli: self.c_pli,
+                result: PhantomData,
+            })
+        }
+    }
+}
+
+// Counts in P7_HIT are ints, but never negative.
+fn count(n: i32) -> usize {
+    usize::try_from(n).unwrap_or(0)
 }
 
-impl Iterator for HmmsearchResultTopHit {
-    type Item = HmmsearchResultDomain;
+impl<'a> Iterator for HmmsearchResultTopHit<'a> {
+    type Item = HmmsearchResultDomain<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
         // TODO: Check for end of hits
-        if self.current_domain >= unsafe { (*self.c_hit).ndom.try_into().unwrap() } {
+        if self.current_domain >= count(unsafe { (*self.c_hit).ndom }) {
             return None;
         }
-        println!("current domain counter {} ", self.current_domain);
         let domain = HmmsearchResultDomain {
             c_dom: unsafe { (*self.c_hit).dcl.add(self.current_domain) },
             c_pli: self.c_pli,
+            result: PhantomData,
         };
 
         self.current_domain += 1;
@@ -613,12 +1209,13 @@ impl Iterator for HmmsearchResultTopHit {
     }
 }
 
-pub struct HmmsearchResultDomain {
+pub struct HmmsearchResultDomain<'a> {
     c_dom: *mut libhmmer_sys::p7_dom_s,
     c_pli: *mut libhmmer_sys::p7_pipeline_s,
+    result: PhantomData<&'a ()>,
 }
 
-impl HmmsearchResultDomain {
+impl HmmsearchResultDomain<'_> {
     // println!("First domain score: {}", first_domain.bitscore);
     pub fn bitscore(&self) -> f32 {
         unsafe { (*self.c_dom).bitscore }
@@ -630,4 +1227,294 @@ impl HmmsearchResultDomain {
     pub fn evalue(&self) -> f64 {
         unsafe { (*self.c_dom).lnP.exp() * (*self.c_pli).Z }
     }
+
+    /// Independent E-value of the domain, calculated with Z, the size of the
+    /// whole search (the "i-Evalue" column). The same as evalue().
+    pub fn independent_evalue(&self) -> f64 {
+        self.evalue()
+    }
+
+    /// Conditional E-value of the domain, calculated with domZ, the number
+    /// of targets that were reported (the "c-Evalue" column).
+    pub fn conditional_evalue(&self) -> f64 {
+        unsafe { (*self.c_dom).lnP.exp() * (*self.c_pli).domZ }
+    }
+
+    /// Natural log of the P-value of the domain score.
+    pub fn ln_pvalue(&self) -> f64 {
+        unsafe { (*self.c_dom).lnP }
+    }
+
+    /// P-value of the domain score.
+    pub fn pvalue(&self) -> f64 {
+        self.ln_pvalue().exp()
+    }
+
+    /// Forward score of the envelope, in nats, before the null model and
+    /// null2 corrections that give bitscore().
+    pub fn envelope_score(&self) -> f32 {
+        unsafe { (*self.c_dom).envsc }
+    }
+
+    /// Composition bias correction of the domain score, in bits (the "bias"
+    /// column of --domtblout).
+    pub fn bias(&self) -> f32 {
+        // th->hit[h]->dcl[d].dombias * eslCONST_LOG2R, /* NATS to BITS at last moment */
+        unsafe { ((*self.c_dom).dombias as f64 * std::f64::consts::LOG2_E) as f32 }
+    }
+
+    /// Mean posterior probability of the aligned residues of the envelope,
+    /// a measure of alignment reliability (the "acc" column of
+    /// --domtblout).
+    pub fn mean_posterior_probability(&self) -> f32 {
+        // oasc / (1.0 + fabs((float) (jenv - ienv)))
+        unsafe {
+            let dom = &*self.c_dom;
+            dom.oasc / (1.0 + (dom.jenv - dom.ienv).abs() as f32)
+        }
+    }
+
+    /// Copy the domain, including its alignment, into a plain Rust value.
+    pub fn to_owned(&self) -> Domain {
+        Domain {
+            env_from: self.env_from(),
+            env_to: self.env_to(),
+            ali_from: self.ali_from(),
+            ali_to: self.ali_to(),
+            hmm_from: self.hmm_from(),
+            hmm_to: self.hmm_to(),
+            bitscore: self.bitscore(),
+            envelope_score: self.envelope_score(),
+            bias: self.bias(),
+            ln_pvalue: self.ln_pvalue(),
+            independent_evalue: self.independent_evalue(),
+            conditional_evalue: self.conditional_evalue(),
+            mean_posterior_probability: self.mean_posterior_probability(),
+            is_reported: self.is_reported(),
+            is_included: self.is_included(),
+            alignment: self.alignment(),
+        }
+    }
+
+    /// The pairwise alignment of the domain to the model, as hmmsearch
+    /// displays it.
+    pub fn alignment(&self) -> DomainAlignment {
+        unsafe { DomainAlignment::from_c_alidisplay((*self.c_dom).ad) }
+    }
+
+    /// Whether the domain meets the domain reporting thresholds.
+    pub fn is_reported(&self) -> bool {
+        unsafe { (*self.c_dom).is_reported != 0 }
+    }
+
+    /// Whether the domain meets the domain inclusion thresholds.
+    pub fn is_included(&self) -> bool {
+        unsafe { (*self.c_dom).is_included != 0 }
+    }
+
+    /// Start of the envelope on the target sequence (1-based, inclusive).
+    pub fn env_from(&self) -> i64 {
+        unsafe { (*self.c_dom).ienv }
+    }
+
+    /// End of the envelope on the target sequence (1-based, inclusive).
+    pub fn env_to(&self) -> i64 {
+        unsafe { (*self.c_dom).jenv }
+    }
+
+    /// Start of the alignment on the target sequence (1-based, inclusive).
+    pub fn ali_from(&self) -> i64 {
+        unsafe { (*self.c_dom).iali }
+    }
+
+    /// End of the alignment on the target sequence (1-based, inclusive).
+    pub fn ali_to(&self) -> i64 {
+        unsafe { (*self.c_dom).jali }
+    }
+
+    /// Start of the alignment on the HMM (1-based, inclusive).
+    pub fn hmm_from(&self) -> i32 {
+        unsafe { (*(*self.c_dom).ad).hmmfrom }
+    }
+
+    /// End of the alignment on the HMM (1-based, inclusive).
+    pub fn hmm_to(&self) -> i32 {
+        unsafe { (*(*self.c_dom).ad).hmmto }
+    }
+}
+
+/// An owned copy of a [`HmmsearchResult`], made with
+/// [`HmmsearchResult::to_owned`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchResult {
+    /// Search space size used for per-sequence and independent domain
+    /// E-values.
+    pub z: f64,
+    /// Search space size used for conditional domain E-values.
+    pub dom_z: f64,
+    /// Numbers of targets that were searched and passed each stage.
+    pub statistics: PipelineStatistics,
+    /// The reported hits, best first.
+    pub hits: Vec<Hit>,
+}
+
+/// Accounting of the targets that went through a pipeline, as summarised at
+/// the end of hmmsearch and hmmscan output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PipelineStatistics {
+    /// Number of models searched.
+    pub nmodels: u64,
+    /// Number of sequences searched.
+    pub nseqs: u64,
+    /// Number of residues searched.
+    pub nres: u64,
+    /// Number of model nodes searched.
+    pub nnodes: u64,
+    /// Number of comparisons that passed the MSV filter.
+    pub n_past_msv: u64,
+    /// Number of comparisons that passed the bias filter.
+    pub n_past_bias: u64,
+    /// Number of comparisons that passed the Viterbi filter.
+    pub n_past_vit: u64,
+    /// Number of comparisons that passed the Forward filter.
+    pub n_past_fwd: u64,
+    /// Number of comparisons whose hits were output.
+    pub n_output: u64,
+}
+
+/// An owned copy of a hit, made with [`HmmsearchResultTopHit::to_owned`].
+/// Fields are as returned by the accessors of the same name.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hit {
+    pub name: String,
+    pub accession: Option<String>,
+    pub description: Option<String>,
+    pub score: f32,
+    pub pre_score: f32,
+    pub sum_score: f32,
+    pub bias: f32,
+    pub evalue: f64,
+    pub ln_pvalue: f64,
+    pub pre_ln_pvalue: f64,
+    pub sum_ln_pvalue: f64,
+    pub nexpected: f32,
+    pub nregions: usize,
+    pub nclustered: usize,
+    pub noverlaps: usize,
+    pub nenvelopes: usize,
+    pub nreported_domains: usize,
+    pub nincluded_domains: usize,
+    pub is_reported: bool,
+    pub is_included: bool,
+    pub is_duplicate: bool,
+    /// Index of the highest scoring domain in `domains`.
+    pub best_domain: Option<usize>,
+    /// All domains of the hit, reported or not.
+    pub domains: Vec<Domain>,
+}
+
+/// An owned copy of a domain, made with [`HmmsearchResultDomain::to_owned`].
+/// Fields are as returned by the accessors of the same name.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Domain {
+    pub env_from: i64,
+    pub env_to: i64,
+    pub ali_from: i64,
+    pub ali_to: i64,
+    pub hmm_from: i32,
+    pub hmm_to: i32,
+    pub bitscore: f32,
+    pub envelope_score: f32,
+    pub bias: f32,
+    pub ln_pvalue: f64,
+    pub independent_evalue: f64,
+    pub conditional_evalue: f64,
+    pub mean_posterior_probability: f32,
+    pub is_reported: bool,
+    pub is_included: bool,
+    pub alignment: DomainAlignment,
+}
+
+/// The alignment of a domain to the model, copied from a P7_ALIDISPLAY.
+///
+/// The alignment lines all have the same length, and line up column by
+/// column as in hmmsearch's domain alignment output. Gaps are shown as '.'
+/// in `model` and '-' in `target`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DomainAlignment {
+    /// Name of the model.
+    pub hmm_name: String,
+    /// Accession of the model, if it has one.
+    pub hmm_accession: Option<String>,
+    /// Description of the model, if it has one.
+    pub hmm_description: Option<String>,
+    /// Start of the alignment on the model (1-based, inclusive).
+    pub hmm_from: i32,
+    /// End of the alignment on the model (1-based, inclusive).
+    pub hmm_to: i32,
+    /// Length of the model.
+    pub hmm_length: i32,
+    /// Name of the target sequence.
+    pub target_name: String,
+    /// Accession of the target sequence, if it has one.
+    pub target_accession: Option<String>,
+    /// Description of the target sequence, if it has one.
+    pub target_description: Option<String>,
+    /// Start of the alignment on the target sequence (1-based, inclusive).
+    pub target_from: i64,
+    /// End of the alignment on the target sequence (1-based, inclusive).
+    pub target_to: i64,
+    /// Length of the target sequence.
+    pub target_length: i64,
+    /// Consensus of the model, upper case for highly conserved positions.
+    pub model: String,
+    /// Match line: the residue where it is identical to the consensus, '+'
+    /// for a positive score and ' ' otherwise.
+    pub match_line: String,
+    /// Aligned target sequence.
+    pub target: String,
+    /// Posterior probability of each aligned residue, '0'-'9' and '*'.
+    pub posterior_probabilities: Option<String>,
+    /// Reference annotation line of the model, if it has one.
+    pub reference_annotation: Option<String>,
+    /// Consensus structure line of the model, if it has one.
+    pub consensus_structure: Option<String>,
+    /// Model mask line, if the model has one.
+    pub model_mask: Option<String>,
+}
+
+impl DomainAlignment {
+    unsafe fn from_c_alidisplay(ad: *const libhmmer_sys::P7_ALIDISPLAY) -> DomainAlignment {
+        let ad = &*ad;
+        DomainAlignment {
+            hmm_name: string(ad.hmmname),
+            hmm_accession: optional_string(ad.hmmacc),
+            hmm_description: optional_string(ad.hmmdesc),
+            hmm_from: ad.hmmfrom,
+            hmm_to: ad.hmmto,
+            hmm_length: ad.M,
+            target_name: string(ad.sqname),
+            target_accession: optional_string(ad.sqacc),
+            target_description: optional_string(ad.sqdesc),
+            target_from: ad.sqfrom,
+            target_to: ad.sqto,
+            target_length: ad.L,
+            model: string(ad.model),
+            match_line: string(ad.mline),
+            target: string(ad.aseq),
+            posterior_probabilities: optional_string(ad.ppline),
+            reference_annotation: optional_string(ad.rfline),
+            consensus_structure: optional_string(ad.csline),
+            model_mask: optional_string(ad.mmline),
+        }
+    }
+}
+
+unsafe fn string(s: *const libc::c_char) -> String {
+    CStr::from_ptr(s).to_string_lossy().into_owned()
 }
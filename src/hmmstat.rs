@@ -0,0 +1,82 @@
+use crate::{EvalueParameters, Hmm, HmmerError};
+
+/// Summary statistics of a model, as reported by `hmmstat`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HmmStats {
+    /// Number of sequences the model was trained on (nseq).
+    pub nseq: Option<u32>,
+    /// Effective number of sequences after weighting (eff_nseq).
+    pub eff_nseq: Option<f32>,
+    /// Number of match states (M).
+    pub length: u32,
+    /// Mean relative entropy of the match emissions against the background,
+    /// in bits per position (relent).
+    pub mean_match_relative_entropy: f64,
+    /// Mean information content of the match emissions, in bits per
+    /// position (info).
+    pub mean_match_information: f64,
+    /// Mean entropy of the match emissions, in bits per position.
+    pub mean_match_entropy: f64,
+    /// Mean relative entropy contributed per position, accounting for
+    /// insert and delete states as well as matches (p relE).
+    pub mean_position_relative_entropy: f64,
+    /// Relative entropy of the model's expected residue composition against
+    /// the background, in bits; a measure of compositional bias (compKL).
+    pub composition_kld: f32,
+    /// E-value calibration parameters, if the model is calibrated.
+    pub evalue_parameters: Option<EvalueParameters>,
+}
+
+impl Hmm {
+    /// Calculate the statistics `hmmstat` reports for this model.
+    pub fn stats(&self) -> Result<HmmStats, HmmerError> {
+        // if (bg == NULL) bg = p7_bg_Create(abc);
+        let bg = unsafe { libhmmer_sys::p7_bg_Create(self.c_alphabet()) };
+        if bg.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
+
+        let result = unsafe { self.stats_with_background(bg) };
+        unsafe { libhmmer_sys::p7_bg_Destroy(bg) };
+        result
+    }
+
+    unsafe fn stats_with_background(
+        &self,
+        bg: *mut libhmmer_sys::P7_BG,
+    ) -> Result<HmmStats, HmmerError> {
+        // p7_MeanPositionRelativeEntropy(hmm, bg, &x);
+        let mut x: f64 = 0.0;
+        let status = libhmmer_sys::p7_MeanPositionRelativeEntropy(self.c_hmm, bg, &mut x);
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_status(
+                status,
+                "p7_MeanPositionRelativeEntropy failed",
+            ));
+        }
+
+        // p7_hmm_CompositionKLD(hmm, bg, &KL, NULL);
+        let mut kl: f32 = 0.0;
+        let status =
+            libhmmer_sys::p7_hmm_CompositionKLD(self.c_hmm, bg, &mut kl, std::ptr::null_mut());
+        if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_status(
+                status,
+                "p7_hmm_CompositionKLD failed",
+            ));
+        }
+
+        Ok(HmmStats {
+            nseq: self.nseq(),
+            eff_nseq: self.eff_nseq(),
+            length: self.length(),
+            mean_match_relative_entropy: libhmmer_sys::p7_MeanMatchRelativeEntropy(self.c_hmm, bg),
+            mean_match_information: libhmmer_sys::p7_MeanMatchInfo(self.c_hmm, bg),
+            mean_match_entropy: libhmmer_sys::p7_MeanMatchEntropy(self.c_hmm),
+            mean_position_relative_entropy: x,
+            composition_kld: kl,
+            evalue_parameters: self.evalue_parameters(),
+        })
+    }
+}
@@ -0,0 +1,302 @@
+use log::*;
+
+use crate::{
+    hmm::Hmm, libhmmer_sys_extras::*, Alphabet, ArchStrategy, EaselMsa, EaselSequence, HmmBuilder,
+    HmmerError, HmmerPipeline, HmmsearchResult, Trace,
+};
+
+/// Iterative search, as the `jackhmmer` command-line tool does: search the
+/// targets, align the included hits, build a new model from that alignment
+/// and repeat, until no new targets are included or the maximum number of
+/// iterations is reached.
+pub struct Jackhmmer {
+    builder: HmmBuilder,
+    max_iterations: usize,
+    pipeline_setup: Option<Box<dyn Fn(HmmerPipeline) -> HmmerPipeline>>,
+}
+
+impl Jackhmmer {
+    /// Create a driver with jackhmmer's default options.
+    pub fn new(alphabet: Alphabet) -> Result<Jackhmmer, HmmerError> {
+        Ok(Jackhmmer {
+            builder: HmmBuilder::new(alphabet)?.with_arch_strategy(ArchStrategy::Hand),
+            max_iterations: 5,
+            pipeline_setup: None,
+        })
+    }
+
+    /// Set the maximum number of iterations (jackhmmer -N). At least one
+    /// iteration is always run. Default: 5
+    pub fn with_max_iterations(self, max_iterations: usize) -> Self {
+        Jackhmmer {
+            max_iterations: max_iterations.max(1),
+            ..self
+        }
+    }
+
+    /// Use the given builder to construct the model for each round, e.g. to
+    /// change the score system or weighting scheme. Models are always built
+    /// from the consensus columns of the previous round's alignment, so the
+    /// builder's architecture strategy is set to ArchStrategy::Hand.
+    pub fn with_builder(self, builder: HmmBuilder) -> Self {
+        Jackhmmer {
+            builder: builder.with_arch_strategy(ArchStrategy::Hand),
+            ..self
+        }
+    }
+
+    /// Configure the pipeline used in each round, e.g. to set reporting or
+    /// inclusion thresholds.
+    pub fn with_pipeline_setup<F>(self, setup: F) -> Self
+    where
+        F: Fn(HmmerPipeline) -> HmmerPipeline + 'static,
+    {
+        Jackhmmer {
+            pipeline_setup: Some(Box::new(setup)),
+            ..self
+        }
+    }
+
+    /// Run an iterative search starting from a single query sequence. The
+    /// first model is built from the query with the builder's score system,
+    /// and the query is included in the alignment for each later round.
+    pub fn search_from_sequence(
+        &mut self,
+        query: &EaselSequence,
+        targets: &[EaselSequence],
+    ) -> Result<JackhmmerResult, HmmerError> {
+        let mut qtr = Trace(std::ptr::null_mut());
+        let hmm = self.builder.single_build(query, &mut qtr.0)?;
+        let query_name = query.name();
+        self.iterate(hmm, Some((query, &qtr)), &query_name, targets)
+    }
+
+    /// Run an iterative search starting from an existing model.
+    pub fn search_from_hmm(
+        &mut self,
+        hmm: &Hmm,
+        targets: &[EaselSequence],
+    ) -> Result<JackhmmerResult, HmmerError> {
+        // p7_hmm_Clone() shares the alphabet, so give the copy one of its own
+        let abc = unsafe { libhmmer_sys::esl_alphabet_Create((*hmm.c_alphabet()).type_) };
+        if abc.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
+        let c_hmm = unsafe { libhmmer_sys::p7_hmm_Clone(hmm.c_hmm) };
+        if c_hmm.is_null() {
+            unsafe { libhmmer_sys::esl_alphabet_Destroy(abc) };
+            return Err(HmmerError::OutOfMemory);
+        }
+        unsafe { (*c_hmm).abc = abc };
+        self.iterate(Hmm { c_hmm }, None, &hmm.name(), targets)
+    }
+
+    fn iterate(
+        &mut self,
+        first_hmm: Hmm,
+        query: Option<(&EaselSequence, &Trace)>,
+        query_name: &str,
+        targets: &[EaselSequence],
+    ) -> Result<JackhmmerResult, HmmerError> {
+        // ESL_KEYHASH     *kh       = NULL;		  /* hash of previous top hits' ranks                */
+        let kh = KeyHash(unsafe { libhmmer_sys::esl_keyhash_Create() });
+        if kh.0.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
+
+        let mut iterations: Vec<JackhmmerIteration> = Vec::new();
+        let mut msa: Option<EaselMsa> = None;
+        let mut hmm = Some(first_hmm);
+        let mut prv_msa_nseq = if query.is_some() { 1 } else { 0 };
+        let mut converged = false;
+
+        for iteration in 1..=self.max_iterations {
+            // Create the search model: from query alone (round 1) or from MSA (round 2+)
+            let hmm = match hmm.take() {
+                Some(hmm) => hmm,
+                None => {
+                    let msa = msa
+                        .as_ref()
+                        .expect("an MSA is made at the end of each round");
+                    prv_msa_nseq = msa.num_sequences();
+                    self.builder.build(msa)?
+                }
+            };
+
+            let mut pipeline = HmmerPipeline::new(&hmm)?;
+            if let Some(setup) = &self.pipeline_setup {
+                pipeline = setup(pipeline);
+            }
+            let result = pipeline.into_search_result(targets)?;
+
+            let mut nnew_targets = 0;
+            let status = unsafe {
+                libhmmer_sys::p7_tophits_CompareRanking(result.c_th, kh.0, &mut nnew_targets)
+            };
+            if status != libhmmer_sys::eslOK as i32 {
+                return Err(HmmerError::from_status(
+                    status,
+                    "p7_tophits_CompareRanking failed",
+                ));
+            }
+
+            // Create alignment of the top hits. The initial query, if any, is
+            // added to the msa at each round.
+            let new_msa = self.align_included_hits(&result, query)?;
+            debug!(
+                "Iteration {}: {} new targets included, alignment has {:?} sequences",
+                iteration,
+                nnew_targets,
+                new_msa.as_ref().map(|m| m.num_sequences())
+            );
+
+            iterations.push(JackhmmerIteration {
+                iteration,
+                new_targets: nnew_targets as usize,
+                result,
+                hmm,
+            });
+
+            // Convergence test
+            let mut new_msa = match new_msa {
+                Some(new_msa) => new_msa,
+                None => {
+                    // Nothing was included, so there is nothing to rebuild from.
+                    converged = true;
+                    break;
+                }
+            };
+            new_msa.set_name(&format!("{}-i{}", query_name, iteration))?;
+            let msa_nseq = new_msa.num_sequences();
+            msa = Some(new_msa);
+            if nnew_targets == 0 && msa_nseq <= prv_msa_nseq {
+                converged = true;
+                break;
+            }
+        }
+
+        Ok(JackhmmerResult {
+            iterations,
+            converged,
+            msa,
+        })
+    }
+
+    /// Align the included domains of a search, and the query if given, as
+    /// p7_tophits_Alignment() does. Returns None if there was nothing to
+    /// align.
+    fn align_included_hits(
+        &self,
+        result: &HmmsearchResult,
+        query: Option<(&EaselSequence, &Trace)>,
+    ) -> Result<Option<EaselMsa>, HmmerError> {
+        let abc = self.builder.alphabet().create_c_alphabet();
+        if abc.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
+        let (mut qsq, mut qtr, inc_n) = match query {
+            Some((seq, tr)) => (seq.c_sq, tr.0, 1),
+            None => (std::ptr::null_mut(), std::ptr::null_mut(), 0),
+        };
+        let mut msa: *mut libhmmer_sys::ESL_MSA = std::ptr::null_mut();
+        let status = unsafe {
+            let status = libhmmer_sys::p7_tophits_Alignment(
+                result.c_th,
+                abc,
+                &mut qsq,
+                &mut qtr,
+                inc_n,
+                p7_ALL_CONSENSUS_COLS,
+                &mut msa,
+            );
+            libhmmer_sys::esl_alphabet_Destroy(abc);
+            status
+        };
+        if status == libhmmer_sys::eslFAIL as i32 {
+            return Ok(None);
+        } else if status != libhmmer_sys::eslOK as i32 {
+            return Err(HmmerError::from_status(
+                status,
+                "Failed to align included hits",
+            ));
+        }
+        Ok(Some(EaselMsa::from_c_msa(msa)))
+    }
+}
+
+/// Results of an iterative search.
+pub struct JackhmmerResult {
+    iterations: Vec<JackhmmerIteration>,
+    converged: bool,
+    msa: Option<EaselMsa>,
+}
+
+impl JackhmmerResult {
+    /// The rounds of the search, in order.
+    pub fn iterations(&self) -> &[JackhmmerIteration] {
+        &self.iterations
+    }
+
+    /// Whether the search converged, i.e. the last round included no new
+    /// targets, rather than stopping at the maximum number of iterations.
+    pub fn converged(&self) -> bool {
+        self.converged
+    }
+
+    /// The model used in the last round.
+    pub fn final_hmm(&self) -> &Hmm {
+        &self
+            .iterations
+            .last()
+            .expect("at least one iteration is always run")
+            .hmm
+    }
+
+    /// Alignment of the query (if the search started from a sequence) and
+    /// the domains included in the last round, or None if nothing was
+    /// included.
+    pub fn msa(&self) -> Option<&EaselMsa> {
+        self.msa.as_ref()
+    }
+}
+
+/// One round of an iterative search.
+pub struct JackhmmerIteration {
+    iteration: usize,
+    new_targets: usize,
+    result: HmmsearchResult<'static>,
+    hmm: Hmm,
+}
+
+impl JackhmmerIteration {
+    /// 1-based round number.
+    pub fn iteration(&self) -> usize {
+        self.iteration
+    }
+
+    /// Number of targets included in this round that were not included in
+    /// the previous one.
+    pub fn new_targets(&self) -> usize {
+        self.new_targets
+    }
+
+    /// Hits found in this round.
+    pub fn result(&self) -> &HmmsearchResult<'static> {
+        &self.result
+    }
+
+    /// Model searched with in this round.
+    pub fn hmm(&self) -> &Hmm {
+        &self.hmm
+    }
+}
+
+struct KeyHash(*mut libhmmer_sys::ESL_KEYHASH);
+
+impl Drop for KeyHash {
+    fn drop(&mut self) {
+        unsafe {
+            libhmmer_sys::esl_keyhash_Destroy(self.0);
+        }
+    }
+}
@@ -1,6 +1,14 @@
+mod error;
 mod hmm;
+mod hmm_parameters;
 mod hmmalign;
+mod hmmbuild;
+mod hmmemit;
+mod hmmpress;
+mod hmmscan;
 mod hmmsearch;
+mod hmmstat;
+mod jackhmmer;
 mod libhmmer_sys_extras;
 #[cfg(feature = "python")]
 mod python;
@@ -9,16 +17,48 @@ use log::*;
 use std::ffi::{CStr, CString};
 use std::fmt::Debug;
 
+pub use crate::error::*;
 pub use crate::hmm::*;
+pub use crate::hmm_parameters::*;
 pub use crate::hmmalign::*;
+pub use crate::hmmbuild::*;
+pub use crate::hmmemit::*;
+pub use crate::hmmscan::*;
 pub use crate::hmmsearch::*;
+pub use crate::hmmstat::*;
+pub use crate::jackhmmer::*;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Alphabet {
     Protein,
     RNA,
     DNA,
 }
 
+impl Alphabet {
+    /// Create a new ESL_ALPHABET of this type. The caller owns it.
+    pub(crate) fn create_c_alphabet(&self) -> *mut libhmmer_sys::ESL_ALPHABET {
+        let alphabet_type = match self {
+            Alphabet::Protein => libhmmer_sys::eslAMINO,
+            Alphabet::RNA => libhmmer_sys::eslRNA,
+            Alphabet::DNA => libhmmer_sys::eslDNA,
+        };
+        unsafe { libhmmer_sys::esl_alphabet_Create(alphabet_type as i32) }
+    }
+
+    /// The alphabet for an ESL_ALPHABET type code, or None for types other
+    /// than amino, DNA and RNA.
+    pub(crate) fn from_c_type(alphabet_type: i32) -> Option<Alphabet> {
+        match alphabet_type as u32 {
+            libhmmer_sys::eslAMINO => Some(Alphabet::Protein),
+            libhmmer_sys::eslRNA => Some(Alphabet::RNA),
+            libhmmer_sys::eslDNA => Some(Alphabet::DNA),
+            _ => None,
+        }
+    }
+}
+
 pub struct EaselSequence {
     // TODO: Implement Drop trait to free this
     pub c_sq: *mut libhmmer_sys::ESL_SQ,
@@ -26,18 +66,7 @@ pub struct EaselSequence {
 
 impl EaselSequence {
     pub fn new(alphabet: Alphabet) -> Self {
-        let c_alphabet = match alphabet {
-            // *const libhmmer_sys::ESL_ALPHABET
-            Alphabet::Protein => unsafe {
-                libhmmer_sys::esl_alphabet_Create(libhmmer_sys::eslAMINO.try_into().unwrap())
-            },
-            Alphabet::RNA => unsafe {
-                libhmmer_sys::esl_alphabet_Create(libhmmer_sys::eslRNA.try_into().unwrap())
-            },
-            Alphabet::DNA => unsafe {
-                libhmmer_sys::esl_alphabet_Create(libhmmer_sys::eslDNA.try_into().unwrap())
-            },
-        };
+        let c_alphabet = alphabet.create_c_alphabet();
         let c_sq = unsafe { libhmmer_sys::esl_sq_CreateDigital(c_alphabet) };
         Self { c_sq }
     }
@@ -51,7 +80,7 @@ impl EaselSequence {
     ///
     /// The seq given here is converted into a newly allocated dsq, so does not
     /// need to live after this function returns.
-    pub fn replace_sequence(&mut self, seq: &[u8]) -> Result<(), &'static str> {
+    pub fn replace_sequence(&mut self, seq: &[u8]) -> Result<(), HmmerError> {
         let n = seq.len() as i64;
 
         unsafe {
@@ -61,6 +90,9 @@ impl EaselSequence {
                 libc::free((*self.c_sq).dsq as *mut libc::c_void);
             }
             (*self.c_sq).dsq = libc::malloc(seq.len() + 2) as *mut u8;
+            if (*self.c_sq).dsq.is_null() {
+                return Err(HmmerError::OutOfMemory);
+            }
 
             // esl_abc_Digitize(const ESL_ALPHABET *a, const char *seq, ESL_DSQ *dsq)
             self.digitise_sequence(seq)?;
@@ -83,20 +115,42 @@ impl EaselSequence {
         Ok(())
     }
 
+    /// The name field of the underlying ESL_SQ (empty if unset).
+    pub fn name(&self) -> String {
+        unsafe {
+            CStr::from_ptr((*self.c_sq).name)
+                .to_string_lossy()
+                .into_owned()
+        }
+    }
+
+    /// The residues of the sequence as text, decoded through the alphabet's
+    /// symbol table as esl_abc_Textize() does.
+    pub fn sequence(&self) -> String {
+        unsafe {
+            let sym = (*(*self.c_sq).abc).sym;
+            (1..=(*self.c_sq).n as usize)
+                .map(|i| *sym.add(*(*self.c_sq).dsq.add(i) as usize) as u8 as char)
+                .collect()
+        }
+    }
+
     /// Set the name field on the underlying ESL_SQ.
     /// The name must not contain null bytes.
-    pub fn set_name(&mut self, name: &str) -> Result<(), &'static str> {
-        let name_cstr = CString::new(name).map_err(|_| "Name must not contain null bytes")?;
+    pub fn set_name(&mut self, name: &str) -> Result<(), HmmerError> {
+        let name_cstr = CString::new(name).map_err(|_| {
+            HmmerError::InvalidArgument("Name must not contain null bytes".to_string())
+        })?;
         let status = unsafe { libhmmer_sys::esl_sq_SetName(self.c_sq, name_cstr.as_ptr()) };
         if status != libhmmer_sys::eslOK as i32 {
-            return Err("esl_sq_SetName failed");
+            return Err(HmmerError::from_status(status, "esl_sq_SetName failed"));
         }
         Ok(())
     }
 
     // Reimplementation of libhmmer_sys::esl_abc_Digitize but don't require a
     // NULL terminated sequence as input. Assumes self.dsq is already allocated.
-    fn digitise_sequence(&mut self, seq: &[u8]) -> Result<(), &'static str> {
+    fn digitise_sequence(&mut self, seq: &[u8]) -> Result<(), HmmerError> {
         // let sstatus = libhmmer_sys::esl_abc_Digitize((*self.c_sq).abc, seq.as_ptr() as *const i8, (*self.c_sq).dsq);
 
         // int     status;
@@ -129,10 +183,10 @@ impl EaselSequence {
 
         // Set actual sequence
         #[allow(non_snake_case)]
-        let Kp: u8 = unsafe { (*(*self.c_sq).abc).Kp.try_into().unwrap() };
+        let Kp = unsafe { (*(*self.c_sq).abc).Kp };
         for (i, s) in seq.iter().enumerate() {
             let x = unsafe { (*(*self.c_sq).abc).inmap[*s as usize] };
-            if x < Kp {
+            if i32::from(x) < Kp {
                 // easel/esl_alphabet.h:#define esl_abc_XGetUnknown(a)       ((a)->Kp)
                 unsafe {
                     *(*self.c_sq).dsq.add(i + 1) = x;
@@ -140,7 +194,9 @@ impl EaselSequence {
             } else if x == libhmmer_sys::eslDSQ_IGNORED as u8 {
                 continue;
             } else {
-                return Err("Invalid character in sequence");
+                return Err(HmmerError::InvalidArgument(
+                    "Invalid character in sequence".to_string(),
+                ));
             }
         }
 
@@ -219,3 +275,26 @@ impl Drop for EaselSequence {
         }
     }
 }
+
+/// Owned P7_TRACE, e.g. the trace of a sampled sequence or of a query
+/// through the model built from it. The pointer may be NULL.
+pub(crate) struct Trace(pub(crate) *mut libhmmer_sys::P7_TRACE);
+
+impl Trace {
+    pub(crate) fn new() -> Result<Trace, HmmerError> {
+        // tr[i] = p7_trace_Create()
+        let tr = unsafe { libhmmer_sys::p7_trace_Create() };
+        if tr.is_null() {
+            return Err(HmmerError::OutOfMemory);
+        }
+        Ok(Trace(tr))
+    }
+}
+
+impl Drop for Trace {
+    fn drop(&mut self) {
+        unsafe {
+            libhmmer_sys::p7_trace_Destroy(self.0);
+        }
+    }
+}
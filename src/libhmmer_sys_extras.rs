@@ -7,10 +7,113 @@
 //  enum p7_zsetby_e    { p7_ZSETBY_NTARGETS = 0, p7_ZSETBY_OPTION = 1, p7_ZSETBY_FILEINFO = 2 };
 //  enum p7_complementarity_e { p7_NOCOMPLEMENT    = 0, p7_COMPLEMENT   = 1 };
 pub const p7_SEARCH_SEQS: i32 = 0;
+pub const p7_SCAN_MODELS: i32 = 1;
+pub const p7_ZSETBY_OPTION: u32 = 1;
 // TODO: Add more, but eh for now.
 
 // #define p7_LOCAL     1		/* multihit local:  "fs" mode   */
 pub const p7_LOCAL: i32 = 1;
+// #define p7_GLOCAL    2		/* multihit glocal: "ls" mode   */
+pub const p7_GLOCAL: i32 = 2;
+// #define p7_UNILOCAL  3		/* unihit local: "sw" mode      */
+pub const p7_UNILOCAL: i32 = 3;
+// #define p7_UNIGLOCAL 4		/* unihit glocal: "s" mode      */
+pub const p7_UNIGLOCAL: i32 = 4;
 
 // #define p7_DEFAULT   0
 pub const p7_DEFAULT: i32 = 0;
+
+// enum p7_hmmfile_formats_e {
+//   p7_HMMFILE_20 = 0,
+//   p7_HMMFILE_3a = 1,
+//   p7_HMMFILE_3b = 2,
+//   p7_HMMFILE_3c = 3,
+//   p7_HMMFILE_3d = 4,
+//   p7_HMMFILE_3e = 5,
+//   p7_HMMFILE_3f = 6,
+// };
+pub const p7_HMMFILE_20: i32 = 0;
+pub const p7_HMMFILE_3a: i32 = 1;
+pub const p7_HMMFILE_3b: i32 = 2;
+pub const p7_HMMFILE_3c: i32 = 3;
+pub const p7_HMMFILE_3d: i32 = 4;
+pub const p7_HMMFILE_3e: i32 = 5;
+pub const p7_HMMFILE_3f: i32 = 6;
+
+// enum p7_archchoice_e { p7_ARCH_FAST = 0, p7_ARCH_HAND = 1 };
+// enum p7_wgtchoice_e  { p7_WGT_NONE  = 0, p7_WGT_GIVEN = 1, p7_WGT_GSC    = 2, p7_WGT_PB       = 3, p7_WGT_BLOSUM = 4 };
+// enum p7_effnchoice_e { p7_EFFN_NONE = 0, p7_EFFN_SET  = 1, p7_EFFN_CLUST = 2, p7_EFFN_ENTROPY = 3, p7_EFFN_ENTROPY_EXP = 4 };
+pub const p7_ARCH_FAST: u32 = 0;
+pub const p7_ARCH_HAND: u32 = 1;
+pub const p7_WGT_NONE: u32 = 0;
+pub const p7_WGT_GIVEN: u32 = 1;
+pub const p7_WGT_GSC: u32 = 2;
+pub const p7_WGT_PB: u32 = 3;
+pub const p7_WGT_BLOSUM: u32 = 4;
+pub const p7_EFFN_NONE: u32 = 0;
+pub const p7_EFFN_SET: u32 = 1;
+pub const p7_EFFN_CLUST: u32 = 2;
+pub const p7_EFFN_ENTROPY: u32 = 3;
+pub const p7_EFFN_ENTROPY_EXP: u32 = 4;
+
+// #define p7_DEFAULT_WINDOW_BETA  1e-7
+pub const p7_DEFAULT_WINDOW_BETA: f64 = 1e-7;
+
+// #define p7_ALL_CONSENSUS_COLS  (1<<1)
+pub const p7_ALL_CONSENSUS_COLS: i32 = 1 << 1;
+
+// enum p7_offsets_e { p7_MOFFSET = 0, p7_FOFFSET = 1, p7_POFFSET = 2 };
+pub const p7_MOFFSET: usize = 0;
+pub const p7_FOFFSET: usize = 1;
+pub const p7_POFFSET: usize = 2;
+
+// #define p7H_RF      (1<<2)    /* #RF annotation available                        !*/
+// #define p7H_CS      (1<<3)    /* #CS annotation available                        !*/
+// #define p7H_STATS   (1<<7)    /* model has E-value statistics calibrated         !*/
+// #define p7H_MAP     (1<<8)    /* alignment map is available                      !*/
+// #define p7H_GA      (1<<10)   /* gathering thresholds available                  !*/
+// #define p7H_TC      (1<<11)   /* trusted cutoffs available                       !*/
+// #define p7H_NC      (1<<12)   /* noise cutoffs available                         !*/
+// #define p7H_COMPO   (1<<14)   /* model-specific residue composition available     */
+// #define p7H_CHKSUM  (1<<15)   /* model has an alignment checksum                  */
+// #define p7H_CONS    (1<<16)   /* consensus residue line available                 */
+// #define p7H_MMASK   (1<<17)   /* #MM annotation available                        !*/
+pub const p7H_RF: i32 = 1 << 2;
+pub const p7H_CS: i32 = 1 << 3;
+pub const p7H_STATS: i32 = 1 << 7;
+pub const p7H_MAP: i32 = 1 << 8;
+pub const p7H_GA: i32 = 1 << 10;
+pub const p7H_TC: i32 = 1 << 11;
+pub const p7H_NC: i32 = 1 << 12;
+pub const p7H_COMPO: i32 = 1 << 14;
+pub const p7H_CHKSUM: i32 = 1 << 15;
+pub const p7H_CONS: i32 = 1 << 16;
+pub const p7H_MMASK: i32 = 1 << 17;
+
+// enum p7_evparams_e {    p7_MMU  = 0, p7_MLAMBDA = 1,     p7_VMU = 2,  p7_VLAMBDA = 3, p7_FTAU = 4, p7_FLAMBDA = 5 };
+// enum p7_cutoffs_e  {     p7_GA1 = 0,     p7_GA2 = 1,     p7_TC1 = 2,      p7_TC2 = 3,  p7_NC1 = 4,     p7_NC2 = 5 };
+pub const p7_MMU: usize = 0;
+pub const p7_MLAMBDA: usize = 1;
+pub const p7_VMU: usize = 2;
+pub const p7_VLAMBDA: usize = 3;
+pub const p7_FTAU: usize = 4;
+pub const p7_FLAMBDA: usize = 5;
+pub const p7_GA1: usize = 0;
+pub const p7_GA2: usize = 1;
+pub const p7_TC1: usize = 2;
+pub const p7_TC2: usize = 3;
+pub const p7_NC1: usize = 4;
+pub const p7_NC2: usize = 5;
+
+// #define p7_CUTOFF_UNSET  -99999.0f  /* if cutoff[XX1] is unset, then cutoff[XX2] unset, XX={GA,TC,NC} */
+pub const p7_CUTOFF_UNSET: f32 = -99999.0;
+
+// #define p7_HITFLAGS_DEFAULT 0
+// #define p7_IS_INCLUDED      (1<<0)
+// #define p7_IS_REPORTED      (1<<1)
+// #define p7_IS_NEW           (1<<2)
+// #define p7_IS_DROPPED       (1<<3)
+// #define p7_IS_DUPLICATE     (1<<4)
+pub const p7_IS_INCLUDED: u32 = 1 << 0;
+pub const p7_IS_REPORTED: u32 = 1 << 1;
+pub const p7_IS_DUPLICATE: u32 = 1 << 4;
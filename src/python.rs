@@ -1,16 +1,36 @@
 use pyo3::prelude::*;
 
-use crate::{Alphabet, EaselSequence, Hmm, HmmerAlign, HmmerPipeline};
+use crate::{Alphabet, EaselSequence, Hmm, HmmerAlign, HmmerError, HmmerPipeline};
+
+impl From<HmmerError> for PyErr {
+    fn from(e: HmmerError) -> PyErr {
+        let msg = e.to_string();
+        // Errors with file context are classified by their underlying cause
+        let kind = match e {
+            HmmerError::HmmFile { source, .. } => *source,
+            e => e,
+        };
+        match kind {
+            HmmerError::NotFound(_) | HmmerError::UnexpectedEof(_) | HmmerError::Write(_) => {
+                pyo3::exceptions::PyIOError::new_err(msg)
+            }
+            HmmerError::Format(_)
+            | HmmerError::InvalidArgument(_)
+            | HmmerError::Incompatible(_)
+            | HmmerError::NoSequences => pyo3::exceptions::PyValueError::new_err(msg),
+            HmmerError::OutOfMemory => pyo3::exceptions::PyMemoryError::new_err(msg),
+            _ => pyo3::exceptions::PyRuntimeError::new_err(msg),
+        }
+    }
+}
 
 /// Convert a list of (name, sequence) Python tuples into EaselSequences.
 fn tuples_to_easel_sequences(sequences: Vec<(String, String)>) -> PyResult<Vec<EaselSequence>> {
     let mut easel_seqs = Vec::with_capacity(sequences.len());
     for (name, seq) in &sequences {
         let mut es = EaselSequence::new(Alphabet::Protein);
-        es.replace_sequence(seq.as_bytes())
-            .map_err(pyo3::exceptions::PyValueError::new_err)?;
-        es.set_name(name)
-            .map_err(pyo3::exceptions::PyValueError::new_err)?;
+        es.replace_sequence(seq.as_bytes())?;
+        es.set_name(name)?;
         easel_seqs.push(es);
     }
     Ok(easel_seqs)
@@ -26,8 +46,7 @@ impl PyHmm {
     /// Read all HMMs from a file, returning a list of Hmm objects.
     #[staticmethod]
     fn read_hmms_from_path(path: &str) -> PyResult<Vec<PyHmm>> {
-        let hmms = Hmm::read_hmms_from_path(std::path::Path::new(path))
-            .map_err(pyo3::exceptions::PyIOError::new_err)?;
+        let hmms = Hmm::read_hmms_from_path(std::path::Path::new(path))?;
         Ok(hmms.into_iter().map(|h| PyHmm { inner: h }).collect())
     }
 
@@ -60,10 +79,10 @@ pub struct PyHmmerPipeline {
 #[pymethods]
 impl PyHmmerPipeline {
     #[new]
-    fn new(hmm: &PyHmm) -> Self {
-        PyHmmerPipeline {
-            inner: HmmerPipeline::new(&hmm.inner),
-        }
+    fn new(hmm: &PyHmm) -> PyResult<Self> {
+        Ok(PyHmmerPipeline {
+            inner: HmmerPipeline::new(&hmm.inner)?,
+        })
     }
 
     /// Set the per-sequence E-value threshold.
@@ -92,7 +111,7 @@ impl PyHmmerPipeline {
         sequences: Vec<(String, String)>,
     ) -> PyResult<Vec<PyHmmsearchHit>> {
         let easel_seqs = tuples_to_easel_sequences(sequences)?;
-        let result = self.inner.search_sequences(&easel_seqs);
+        let result = self.inner.search_sequences(&easel_seqs)?;
         let mut hits = Vec::new();
         for hit in result.hits() {
             hits.push(PyHmmsearchHit {
@@ -108,7 +127,7 @@ impl PyHmmerPipeline {
     fn search_fasta_file(&mut self, path: &str, hmm: &PyHmm) -> PyResult<Vec<PyHmmsearchHit>> {
         let result = self
             .inner
-            .run_hmm_on_file(&hmm.inner, std::path::Path::new(path));
+            .run_hmm_on_file(&hmm.inner, std::path::Path::new(path))?;
         let mut hits = Vec::new();
         for hit in result.hits() {
             hits.push(PyHmmsearchHit {
@@ -138,9 +157,7 @@ impl PyHmmerAlign {
     /// Align sequences and return Stockholm-format string.
     fn align_sequences(&self, sequences: Vec<(String, String)>) -> PyResult<String> {
         let easel_seqs = tuples_to_easel_sequences(sequences)?;
-        self.inner
-            .align_sequences_into_stockholm(&easel_seqs)
-            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+        Ok(self.inner.align_sequences_into_stockholm(&easel_seqs)?)
     }
 }
 
@@ -6,6 +6,27 @@ use log::*;
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    /// The ribosomal protein L2 (rplB) model that most tests search with.
+    fn rplb_hmm() -> Hmm {
+        Hmm::read_hmms_from_path(std::path::Path::new(
+            "tests/data/DNGNGWU00010_mingle_output_good_seqs.hmm",
+        ))
+        .unwrap()
+        .remove(0)
+    }
+
+    /// An rplB protein sequence that rplb_hmm() hits once.
+    fn query_seq(name: &str) -> EaselSequence {
+        let mut seq = EaselSequence::new(Alphabet::Protein);
+        seq.replace_sequence(
+            b"MVYSGPNAPIEVGNSLPLSEIPLATEIHNIELTPGKGGQLVRSAGSSAQLLAKEGNYVTLRLPSGEMRFVRKECYATIGQ",
+        )
+        .unwrap();
+        seq.set_name(name).unwrap();
+        seq
+    }
+
     #[test]
     fn hmmsearch_on_file() {
         let hmms = Hmm::read_hmms_from_path(std::path::Path::new(
@@ -18,12 +39,14 @@ mod tests {
             CStr::from_ptr((*hmm.c_hmm).name).to_string_lossy()
         });
 
-        let mut hmmsearch = HmmerPipeline::new(hmm);
+        let mut hmmsearch = HmmerPipeline::new(hmm).unwrap();
 
-        let hmmsearch_result = hmmsearch.run_hmm_on_file(
-            hmm,
-            std::path::Path::new("tests/data/graftm4o5_y58f.head2.faa"),
-        );
+        let hmmsearch_result = hmmsearch
+            .run_hmm_on_file(
+                hmm,
+                std::path::Path::new("tests/data/graftm4o5_y58f.head2.faa"),
+            )
+            .unwrap();
 
         debug!("HMMsearch result: {:?}", hmmsearch_result);
 
@@ -57,7 +80,7 @@ mod tests {
 
         println!("HMM name: {}", hmm.name());
 
-        let mut hmmsearch = HmmerPipeline::new(hmm);
+        let mut hmmsearch = HmmerPipeline::new(hmm).unwrap();
 
         let mut query_seq = EaselSequence::new(Alphabet::Protein);
         let seq: &[u8] =
@@ -66,9 +89,9 @@ mod tests {
         query_seq.replace_sequence(seq).unwrap();
         debug!("Query seq replaced;");
 
-        hmmsearch.query(&query_seq);
+        hmmsearch.query(&query_seq).unwrap();
 
-        let hmmsearch_result = hmmsearch.get_results();
+        let hmmsearch_result = hmmsearch.get_results().unwrap();
 
         debug!("HMMsearch result: {:?}", hmmsearch_result);
 
@@ -92,6 +115,105 @@ mod tests {
         assert_eq!(1, num_hits);
     }
 
+    #[test]
+    fn test_get_results_can_be_called_multiple_times() {
+        let hmm = rplb_hmm();
+        let mut pipeline = HmmerPipeline::new(&hmm).unwrap();
+
+        let mut seq = query_seq("seq1");
+
+        pipeline.query(&seq).unwrap();
+        let result1 = pipeline.get_results().unwrap().to_owned();
+        assert_eq!(1, result1.hits.len());
+
+        // Each result takes its own hits, so later queries start from an
+        // empty list
+        seq.set_name("seq2").unwrap();
+        pipeline.query(&seq).unwrap();
+        let result2 = pipeline.get_results().unwrap();
+        assert_eq!(1, result2.nreported());
+        assert_eq!("seq2", result2.hits().next().unwrap().name());
+        assert_eq!("seq1", result1.hits[0].name);
+    }
+
+    #[test]
+    fn test_owned_results() {
+        let hmm = rplb_hmm();
+        let mut pipeline = HmmerPipeline::new(&hmm).unwrap();
+
+        let seq = query_seq("seq1");
+        let sequences = vec![seq];
+
+        let result = pipeline.search_sequences(&sequences).unwrap();
+        let owned = result.to_owned();
+        drop(result);
+        // Reusing the pipeline does not affect the owned copy
+        pipeline.search_sequences(&sequences).unwrap();
+
+        // Owned results can be moved to another thread
+        let owned = std::thread::spawn(move || owned).join().unwrap();
+
+        assert_eq!(1.0, owned.z);
+        assert_eq!(1, owned.statistics.nseqs);
+        assert_eq!(1, owned.statistics.n_output);
+        assert_eq!(1, owned.hits.len());
+        let hit = &owned.hits[0];
+        assert_eq!("seq1", hit.name);
+        assert_eq!(None, hit.accession);
+        assert_eq!(150.01991, hit.score);
+        assert!(hit.is_reported);
+        assert_eq!(1, hit.domains.len());
+        assert_eq!(Some(0), hit.best_domain);
+        let domain = &hit.domains[0];
+        assert_eq!(149.90887, domain.bitscore);
+        assert_eq!(1.4970530541655288e-48, domain.independent_evalue);
+        assert_eq!("seq1", domain.alignment.target_name);
+        assert_eq!(domain.ali_from, domain.alignment.target_from);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_types() {
+        fn assert_serde<T: serde::Serialize + serde::de::DeserializeOwned>() {}
+        assert_serde::<SearchResult>();
+        assert_serde::<Hit>();
+        assert_serde::<Domain>();
+        assert_serde::<DomainAlignment>();
+        assert_serde::<PipelineStatistics>();
+        assert_serde::<PipelineConfig>();
+        assert_serde::<HmmStats>();
+        assert_serde::<HmmMetadata>();
+        assert_serde::<ScoreCutoffs>();
+        assert_serde::<EvalueParameters>();
+        assert_serde::<Alphabet>();
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let hmm = rplb_hmm();
+
+        let seq = query_seq("seq1");
+
+        let mut pipeline = HmmerPipeline::new(&hmm).unwrap();
+        let result = pipeline.search_sequences(&[seq]).unwrap().to_owned();
+        assert_eq!(1, result.hits.len());
+
+        let json = serde_json::to_string(&result).unwrap();
+        let round_tripped: SearchResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_tripped, result);
+        assert_eq!(round_tripped.hits[0].name, "seq1");
+        assert_eq!(
+            round_tripped.hits[0].domains[0].alignment,
+            result.hits[0].domains[0].alignment
+        );
+
+        let metadata = hmm.metadata();
+        let json = serde_json::to_string(&metadata).unwrap();
+        let round_tripped: HmmMetadata = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_tripped, metadata);
+    }
+
     #[test]
     fn test_set_name() {
         let mut seq = EaselSequence::new(Alphabet::Protein);
@@ -114,7 +236,7 @@ mod tests {
         .unwrap();
         let hmm = &hmms[0];
 
-        let mut pipeline = HmmerPipeline::new(hmm);
+        let mut pipeline = HmmerPipeline::new(hmm).unwrap();
 
         let mut query_seq = EaselSequence::new(Alphabet::Protein);
         query_seq
@@ -124,8 +246,8 @@ mod tests {
             .unwrap();
         query_seq.set_name("test_seq_name").unwrap();
 
-        pipeline.query(&query_seq);
-        let result = pipeline.get_results();
+        pipeline.query(&query_seq).unwrap();
+        let result = pipeline.get_results().unwrap();
 
         assert_eq!(1, result.nreported());
         for hit in result.hits() {
@@ -141,7 +263,7 @@ mod tests {
         .unwrap();
         let hmm = &hmms[0];
 
-        let mut pipeline = HmmerPipeline::new(hmm);
+        let mut pipeline = HmmerPipeline::new(hmm).unwrap();
 
         let mut seq1 = EaselSequence::new(Alphabet::Protein);
         seq1.replace_sequence(
@@ -155,7 +277,7 @@ mod tests {
         seq2.set_name("seq2_no_hit").unwrap();
 
         let sequences = vec![seq1, seq2];
-        let result = pipeline.search_sequences(&sequences);
+        let result = pipeline.search_sequences(&sequences).unwrap();
 
         assert_eq!(1, result.nreported());
         for hit in result.hits() {
@@ -172,7 +294,7 @@ mod tests {
         .unwrap();
         let hmm = &hmms[0];
 
-        let mut pipeline = HmmerPipeline::new(hmm);
+        let mut pipeline = HmmerPipeline::new(hmm).unwrap();
 
         let mut seq1 = EaselSequence::new(Alphabet::Protein);
         seq1.replace_sequence(
@@ -184,12 +306,12 @@ mod tests {
         let sequences = vec![seq1];
 
         // First call
-        let result1 = pipeline.search_sequences(&sequences);
+        let result1 = pipeline.search_sequences(&sequences).unwrap();
         assert_eq!(1, result1.nreported());
         drop(result1);
 
         // Second call on same pipeline should also work
-        let result2 = pipeline.search_sequences(&sequences);
+        let result2 = pipeline.search_sequences(&sequences).unwrap();
         assert_eq!(1, result2.nreported());
     }
 
@@ -203,7 +325,7 @@ mod tests {
 
         // With a very strict E-value threshold, the hit should still pass
         // (it has a very strong e-value ~1.5e-48)
-        let mut pipeline = HmmerPipeline::new(hmm).with_dom_evalue(1e-40);
+        let mut pipeline = HmmerPipeline::new(hmm).unwrap().with_dom_evalue(1e-40);
 
         let mut seq = EaselSequence::new(Alphabet::Protein);
         seq.replace_sequence(
@@ -212,7 +334,7 @@ mod tests {
         .unwrap();
         seq.set_name("good_hit").unwrap();
 
-        let result = pipeline.search_sequences(&[seq]);
+        let result = pipeline.search_sequences(&[seq]).unwrap();
         assert_eq!(1, result.nreported());
     }
 
@@ -225,7 +347,7 @@ mod tests {
         let hmm = &hmms[0];
 
         // With an impossibly strict E-value, nothing should pass
-        let mut pipeline = HmmerPipeline::new(hmm).with_seq_evalue(1e-200);
+        let mut pipeline = HmmerPipeline::new(hmm).unwrap().with_seq_evalue(1e-200);
 
         let mut seq = EaselSequence::new(Alphabet::Protein);
         seq.replace_sequence(
@@ -234,10 +356,156 @@ mod tests {
         .unwrap();
         seq.set_name("test").unwrap();
 
-        let result = pipeline.search_sequences(&[seq]);
+        let result = pipeline.search_sequences(&[seq]).unwrap();
+        assert_eq!(0, result.nreported());
+    }
+
+    #[test]
+    fn test_gathering_cutoffs() {
+        let mut hmm = rplb_hmm();
+
+        // The model has no cutoffs, so asking for them is an error
+        let result = HmmerPipeline::new(&hmm).unwrap().with_gathering_cutoffs();
+        assert!(matches!(result, Err(HmmerError::InvalidArgument(_))));
+
+        let seq = query_seq("test");
+
+        // The hit scores ~150 bits, so passes GA but not TC
+        hmm.set_gathering_cutoffs(Some(ScoreCutoffs {
+            sequence: 25.0,
+            domain: 25.0,
+        }));
+        hmm.set_trusted_cutoffs(Some(ScoreCutoffs {
+            sequence: 500.0,
+            domain: 500.0,
+        }));
+
+        let mut pipeline = HmmerPipeline::new(&hmm)
+            .unwrap()
+            .with_gathering_cutoffs()
+            .unwrap();
+        let result = pipeline
+            .search_sequences(std::slice::from_ref(&seq))
+            .unwrap();
+        assert_eq!(1, result.nreported());
+
+        let mut pipeline = HmmerPipeline::new(&hmm)
+            .unwrap()
+            .with_trusted_cutoffs()
+            .unwrap();
+        let result = pipeline.search_sequences(&[seq]).unwrap();
+        assert_eq!(0, result.nreported());
+    }
+
+    #[test]
+    fn test_inclusion_thresholds() {
+        let hmm = rplb_hmm();
+
+        let seq = query_seq("test");
+
+        // With default thresholds the hit (E ~1e-48) is reported and included
+        let mut pipeline = HmmerPipeline::new(&hmm).unwrap();
+        let result = pipeline
+            .search_sequences(std::slice::from_ref(&seq))
+            .unwrap();
+        let mut hit = result.hits().next().unwrap();
+        assert!(hit.is_reported());
+        assert!(hit.is_included());
+        let domain = hit.next().unwrap();
+        assert!(domain.is_reported());
+        assert!(domain.is_included());
+
+        // Stricter inclusion thresholds leave it reported but not included
+        let mut pipeline = HmmerPipeline::new(&hmm)
+            .unwrap()
+            .with_seq_inclusion_evalue(1e-100)
+            .with_dom_inclusion_bitscore(1000.0);
+        let result = pipeline
+            .search_sequences(std::slice::from_ref(&seq))
+            .unwrap();
+        assert_eq!(1, result.nreported());
+        let mut hit = result.hits().next().unwrap();
+        assert!(hit.is_reported());
+        assert!(!hit.is_included());
+        let domain = hit.next().unwrap();
+        assert!(domain.is_reported());
+        assert!(!domain.is_included());
+
+        // Reporting by bit score
+        let mut pipeline = HmmerPipeline::new(&hmm).unwrap().with_seq_bitscore(1000.0);
+        let result = pipeline.search_sequences(&[seq]).unwrap();
         assert_eq!(0, result.nreported());
     }
 
+    #[test]
+    fn test_fixed_database_size() {
+        let hmm = rplb_hmm();
+
+        let seq = query_seq("test");
+
+        let mut pipeline = HmmerPipeline::new(&hmm).unwrap();
+        let result = pipeline
+            .search_sequences(std::slice::from_ref(&seq))
+            .unwrap();
+        assert_eq!(1.0, result.z());
+        assert_eq!(1.0, result.dom_z());
+        let evalue = result.hits().next().unwrap().evalue();
+
+        let mut pipeline = HmmerPipeline::new(&hmm)
+            .unwrap()
+            .with_z(2e8)
+            .with_dom_z(10.0);
+        let result = pipeline.search_sequences(&[seq]).unwrap();
+        assert_eq!(2e8, result.z());
+        assert_eq!(10.0, result.dom_z());
+        let scaled = result.hits().next().unwrap().evalue();
+        assert!((scaled / evalue - 2e8).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_pipeline_config() {
+        let hmm = rplb_hmm();
+
+        let seq1 = query_seq("seq1");
+        let mut seq2 = EaselSequence::new(Alphabet::Protein);
+        seq2.replace_sequence(b"AAAAAAAAAAAAAAAAAAAA").unwrap();
+        seq2.set_name("seq2_no_hit").unwrap();
+        let sequences = vec![seq1, seq2];
+
+        // With the filters off, every sequence reaches Forward
+        let config = PipelineConfig {
+            max_sensitivity: true,
+            null2: false,
+            domain_samples: 100,
+            ..Default::default()
+        };
+        let mut pipeline = HmmerPipeline::new(&hmm).unwrap().with_config(&config);
+        let nreported = pipeline.search_sequences(&sequences).unwrap().nreported();
+        assert_eq!(1, nreported);
+        let pli = pipeline.pli();
+        unsafe {
+            assert_eq!(1, (*pli).do_max);
+            assert_eq!(0, (*pli).do_biasfilter);
+            assert_eq!(0, (*pli).do_null2);
+            assert_eq!(1.0, (*pli).F1);
+            assert_eq!(100, (*(*pli).ddef).nsamples);
+            assert_eq!(2, (*pli).n_past_fwd);
+        }
+
+        // Default settings are HMMER's
+        let mut pipeline = HmmerPipeline::new(&hmm)
+            .unwrap()
+            .with_config(&PipelineConfig::default());
+        let pli = pipeline.pli();
+        unsafe {
+            assert_eq!(0.02, (*pli).F1);
+            assert_eq!(1e-3, (*pli).F2);
+            assert_eq!(1e-5, (*pli).F3);
+            assert_eq!(1, (*pli).do_biasfilter);
+            assert_eq!(0.25, (*(*pli).ddef).rt1);
+        }
+    }
+
     #[test]
     fn test_hit_evalue() {
         let hmms = Hmm::read_hmms_from_path(std::path::Path::new(
@@ -246,7 +514,7 @@ mod tests {
         .unwrap();
         let hmm = &hmms[0];
 
-        let mut pipeline = HmmerPipeline::new(hmm);
+        let mut pipeline = HmmerPipeline::new(hmm).unwrap();
 
         let mut seq = EaselSequence::new(Alphabet::Protein);
         seq.replace_sequence(
@@ -255,7 +523,7 @@ mod tests {
         .unwrap();
         seq.set_name("test").unwrap();
 
-        let result = pipeline.search_sequences(&[seq]);
+        let result = pipeline.search_sequences(&[seq]).unwrap();
         assert_eq!(1, result.nreported());
 
         for hit in result.hits() {
@@ -267,6 +535,99 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_hit_accessors() {
+        let hmm = rplb_hmm();
+
+        let mut pipeline = HmmerPipeline::new(&hmm).unwrap();
+
+        let seq = query_seq("test");
+
+        let result = pipeline.search_sequences(&[seq]).unwrap();
+        let hit = result.hits().next().unwrap();
+
+        assert_eq!("", hit.acc());
+        assert_eq!("", hit.description());
+        assert_eq!(hit.bias(), hit.pre_score() - hit.score());
+        assert!(hit.bias() >= 0.0);
+        assert!(hit.pvalue() > 0.0 && hit.pvalue() < 1e-40);
+        assert_eq!(hit.evalue(), hit.pvalue() * result.z());
+        assert!(hit.nexpected() > 0.0);
+        assert_eq!(1, hit.nregions());
+        assert_eq!(1, hit.nenvelopes());
+        assert_eq!(1, hit.ndom());
+        assert_eq!(1, hit.nreported_domains());
+        assert_eq!(1, hit.nincluded_domains());
+        assert!(!hit.is_duplicate());
+
+        let best = hit.best_domain().unwrap();
+        assert_eq!(149.90887, best.bitscore());
+    }
+
+    #[test]
+    fn test_domain_accessors() {
+        let hmm = rplb_hmm();
+
+        let mut pipeline = HmmerPipeline::new(&hmm).unwrap().with_z(100.0);
+
+        let sequence =
+            b"MVYSGPNAPIEVGNSLPLSEIPLATEIHNIELTPGKGGQLVRSAGSSAQLLAKEGNYVTLRLPSGEMRFVRKECYATIGQ";
+        let mut seq = EaselSequence::new(Alphabet::Protein);
+        seq.replace_sequence(sequence).unwrap();
+        seq.set_name("test").unwrap();
+
+        let result = pipeline.search_sequences(&[seq]).unwrap();
+        let domain = result.hits().next().unwrap().next().unwrap();
+
+        assert!(domain.env_from() >= 1);
+        assert!(domain.env_from() <= domain.ali_from());
+        assert!(domain.ali_to() <= domain.env_to());
+        assert!(domain.env_to() <= sequence.len() as i64);
+        assert!(domain.hmm_from() >= 1);
+        assert!(domain.hmm_from() < domain.hmm_to());
+        assert!(domain.hmm_to() <= hmm.length() as i32);
+
+        // Z was fixed at 100, while domZ is the one reported sequence
+        assert_eq!(domain.evalue(), domain.independent_evalue());
+        assert_eq!(domain.independent_evalue(), domain.pvalue() * 100.0);
+        assert_eq!(domain.conditional_evalue(), domain.pvalue());
+        assert!(domain.envelope_score() > 0.0);
+        assert!(domain.bias() >= 0.0);
+        let acc = domain.mean_posterior_probability();
+        assert!(acc > 0.5 && acc <= 1.0, "acc was {}", acc);
+        assert!(domain.is_reported());
+        assert!(domain.is_included());
+
+        let alignment = domain.alignment();
+        assert_eq!(hmm.name(), alignment.hmm_name);
+        assert_eq!(None, alignment.hmm_accession);
+        assert_eq!(domain.hmm_from(), alignment.hmm_from);
+        assert_eq!(domain.hmm_to(), alignment.hmm_to);
+        assert_eq!(hmm.length() as i32, alignment.hmm_length);
+        assert_eq!("test", alignment.target_name);
+        assert_eq!(domain.ali_from(), alignment.target_from);
+        assert_eq!(domain.ali_to(), alignment.target_to);
+        assert_eq!(sequence.len() as i64, alignment.target_length);
+        let n = alignment.model.len();
+        assert_eq!(n, alignment.match_line.len());
+        assert_eq!(n, alignment.target.len());
+        assert_eq!(
+            Some(n),
+            alignment.posterior_probabilities.map(|pp| pp.len())
+        );
+        let aligned: String = alignment
+            .target
+            .chars()
+            .filter(|c| c.is_ascii_alphabetic())
+            .collect();
+        let from = alignment.target_from as usize - 1;
+        let to = alignment.target_to as usize;
+        assert_eq!(
+            std::str::from_utf8(&sequence[from..to]).unwrap(),
+            aligned.to_uppercase()
+        );
+    }
+
     #[test]
     fn test_hmmalign() {
         let hmms = Hmm::read_hmms_from_path(std::path::Path::new(
@@ -292,7 +653,7 @@ mod tests {
 
         // The aligned sequence should contain the original residues (possibly with gaps)
         let aligned = msa.aligned_sequence(0);
-        assert!(aligned.len() > 0);
+        assert!(!aligned.is_empty());
         // Removing gaps and uppercasing should recover the original sequence
         // (HMMER uses lowercase for insert-state residues)
         let ungapped: String = aligned
@@ -407,4 +768,693 @@ mod tests {
         let result = aligner.align_sequences(&[]);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_read_hmms_missing_file() {
+        let result =
+            Hmm::read_hmms_from_path(std::path::Path::new("tests/data/does_not_exist.hmm"));
+        match result {
+            Err(HmmerError::HmmFile {
+                model_index,
+                source,
+                ..
+            }) => {
+                assert_eq!(model_index, None);
+                match *source {
+                    HmmerError::NotFound(msg) => {
+                        assert!(msg.contains("does_not_exist.hmm"), "{msg}")
+                    }
+                    e => panic!("Unexpected source error {e:?}"),
+                }
+            }
+            Err(e) => panic!("Unexpected error {e:?}"),
+            Ok(_) => panic!("Expected an error"),
+        }
+    }
+
+    #[test]
+    fn test_read_hmms_corrupted_file() {
+        let result = Hmm::read_hmms_from_path(std::path::Path::new(
+            "tests/data/corrupted_second_model.hmm",
+        ));
+        let err = match result {
+            Err(e) => e,
+            Ok(_) => panic!("Expected an error"),
+        };
+        match &err {
+            HmmerError::HmmFile {
+                path,
+                model_index,
+                line,
+                source,
+            } => {
+                assert!(path.ends_with("corrupted_second_model.hmm"), "{path}");
+                assert_eq!(*model_index, Some(1));
+                assert_eq!(*line, Some(464));
+                assert!(
+                    matches!(**source, HmmerError::Format(ref msg) if msg.contains("saw 7")),
+                    "{source:?}"
+                );
+            }
+            e => panic!("Unexpected error {e:?}"),
+        }
+        assert_eq!(err.status(), Some(7)); // eslEFORMAT
+        assert!(err.to_string().contains("model 1, line 464"), "{err}");
+    }
+
+    #[test]
+    fn test_hmm_reader() {
+        let reader = HmmReader::from_path(std::path::Path::new(
+            "tests/data/DNGNGWU00010_mingle_output_good_seqs.hmm",
+        ))
+        .unwrap();
+        let names: Vec<String> = reader.map(|hmm| hmm.unwrap().name()).collect();
+        assert_eq!(names, vec!["S2.1.ribosomal_protein_L2_rplB".to_string()]);
+    }
+
+    #[test]
+    fn test_hmm_reader_corrupted_file() {
+        let mut reader = HmmReader::from_path(std::path::Path::new(
+            "tests/data/corrupted_second_model.hmm",
+        ))
+        .unwrap();
+        assert!(reader.next().unwrap().is_ok());
+        assert!(matches!(
+            reader.next(),
+            Some(Err(HmmerError::HmmFile {
+                model_index: Some(1),
+                ..
+            }))
+        ));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_read_hmms_from_str() {
+        let text = [
+            include_str!("data/DNGNGWU00010_mingle_output_good_seqs.hmm"),
+            include_str!("data/DNGNGWU00030_mingle_output_good_seqs.hmm"),
+        ]
+        .concat();
+        let hmms = Hmm::read_hmms_from_str(&text).unwrap();
+        assert_eq!(hmms.len(), 2);
+        assert_eq!(hmms[0].name(), "S2.1.ribosomal_protein_L2_rplB");
+        assert_eq!(
+            hmms[1].name(),
+            "DNGNGWU00030_mingle_output_good_seqs_MJ6Mc8.aln"
+        );
+    }
+
+    #[test]
+    fn test_read_hmms_from_reader() {
+        let file =
+            std::fs::File::open("tests/data/DNGNGWU00010_mingle_output_good_seqs.hmm").unwrap();
+        let hmms = Hmm::read_hmms_from_reader(file).unwrap();
+        assert_eq!(hmms.len(), 1);
+        assert_eq!(hmms[0].name(), "S2.1.ribosomal_protein_L2_rplB");
+        assert_eq!(hmms[0].length(), 139);
+    }
+
+    #[test]
+    fn test_read_hmms_from_bytes_not_hmm() {
+        let result = Hmm::read_hmms_from_bytes(b">seq1\nMVYSGPN\n");
+        assert!(matches!(
+            result,
+            Err(HmmerError::HmmFile {
+                model_index: None,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_hmm_metadata() {
+        let hmm = rplb_hmm();
+
+        assert_eq!(hmm.alphabet(), Some(Alphabet::Protein));
+        assert_eq!(hmm.nseq(), Some(21752));
+        assert!((hmm.eff_nseq().unwrap() - 5.764329).abs() < 1e-5);
+        assert_eq!(hmm.checksum(), Some(3809638471));
+        assert_eq!(
+            hmm.creation_time(),
+            Some("Wed Apr 21 07:23:45 2021".to_string())
+        );
+        assert_eq!(hmm.command_log(), None);
+        assert_eq!(hmm.max_length(), None);
+        assert_eq!(hmm.gathering_cutoffs(), None);
+        assert_eq!(hmm.trusted_cutoffs(), None);
+        assert_eq!(hmm.noise_cutoffs(), None);
+
+        let stats = hmm.evalue_parameters().unwrap();
+        assert!((stats.msv_mu - -10.5057).abs() < 1e-4);
+        assert!((stats.viterbi_mu - -10.4478).abs() < 1e-4);
+        assert!((stats.forward_tau - -5.0356).abs() < 1e-4);
+        assert!((stats.forward_lambda - 0.71071).abs() < 1e-4);
+
+        let consensus = hmm.consensus().unwrap();
+        assert_eq!(consensus.len(), 139);
+        assert!(consensus.starts_with("rd"));
+        assert_eq!(hmm.reference_annotation().unwrap().len(), 139);
+        assert_eq!(hmm.model_mask(), None);
+        assert_eq!(hmm.consensus_structure(), None);
+        let map = hmm.alignment_map().unwrap();
+        assert_eq!(map.len(), 139);
+        assert_eq!(&map[..2], &[1, 2]);
+
+        let metadata = hmm.metadata();
+        assert_eq!(metadata.name, "S2.1.ribosomal_protein_L2_rplB");
+        assert_eq!(metadata.accession, None);
+        assert_eq!(metadata.description, None);
+        assert_eq!(metadata.length, 139);
+        assert_eq!(metadata.alphabet, Some(Alphabet::Protein));
+        assert_eq!(metadata.nseq, Some(21752));
+        assert_eq!(metadata.eff_nseq, hmm.eff_nseq());
+        assert_eq!(metadata.gathering_cutoffs, None);
+        assert_eq!(metadata.evalue_parameters, Some(stats));
+    }
+
+    #[test]
+    fn test_hmm_stats() {
+        let hmm = rplb_hmm();
+        let stats = hmm.stats().unwrap();
+
+        assert_eq!(stats.nseq, Some(21752));
+        assert_eq!(stats.length, 139);
+        assert!(stats.mean_match_relative_entropy > 0.0);
+        assert!(stats.mean_match_information > 0.0);
+        // Protein entropy is at most log2(20) bits
+        assert!(stats.mean_match_entropy > 0.0 && stats.mean_match_entropy < 20f64.log2());
+        assert!(stats.mean_position_relative_entropy > 0.0);
+        assert!(stats.composition_kld >= 0.0);
+        assert_eq!(stats.evalue_parameters, hmm.evalue_parameters());
+    }
+
+    #[test]
+    fn test_hmmemit() {
+        let hmm = rplb_hmm();
+
+        let first = HmmEmitter::new(&hmm, 42)
+            .unwrap()
+            .emit_sequences(3)
+            .unwrap();
+        let second = HmmEmitter::new(&hmm, 42)
+            .unwrap()
+            .emit_sequences(3)
+            .unwrap();
+        assert_eq!(first.len(), 3);
+        assert_eq!(first[2].name(), format!("{}-sample3", hmm.name()));
+        for (a, b) in first.iter().zip(&second) {
+            assert!(!a.sequence().is_empty());
+            assert_eq!(a.sequence(), b.sequence());
+        }
+
+        let profile = HmmEmitter::new(&hmm, 42)
+            .unwrap()
+            .with_profile_mode(ProfileMode::Unilocal, 400)
+            .emit_sequences(2)
+            .unwrap();
+        assert_eq!(profile.len(), 2);
+        assert!(matches!(
+            HmmEmitter::new(&hmm, 42)
+                .unwrap()
+                .with_profile_mode(ProfileMode::Local, u32::MAX)
+                .emit_sequences(1),
+            Err(HmmerError::InvalidArgument(_))
+        ));
+
+        let msa = HmmEmitter::new(&hmm, 42)
+            .unwrap()
+            .emit_alignment(4)
+            .unwrap();
+        assert_eq!(msa.num_sequences(), 4);
+        assert!(msa.alignment_length() >= 139);
+
+        let consensus = hmm.consensus_sequence().unwrap();
+        assert_eq!(consensus.name(), format!("{}-consensus", hmm.name()));
+        assert_eq!(consensus.sequence().len(), 139);
+        let fancy = hmm.fancy_consensus_sequence(0.0, 0.5).unwrap();
+        assert_eq!(fancy.to_uppercase(), consensus.sequence());
+    }
+
+    #[test]
+    fn test_hmm_setters() {
+        let mut hmm = rplb_hmm();
+        let composition = hmm.composition().unwrap();
+
+        hmm.set_name("rplB").unwrap();
+        hmm.set_accession(Some("PF00181.1")).unwrap();
+        hmm.set_description(Some("Ribosomal protein L2")).unwrap();
+        hmm.set_gathering_cutoffs(Some(ScoreCutoffs {
+            sequence: 25.0,
+            domain: 20.5,
+        }));
+        hmm.set_trusted_cutoffs(Some(ScoreCutoffs {
+            sequence: 30.0,
+            domain: 30.0,
+        }));
+        hmm.set_trusted_cutoffs(None);
+        hmm.update_composition().unwrap();
+        assert!(matches!(
+            hmm.set_name("two words"),
+            Err(HmmerError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            hmm.set_description(Some("two\nlines")),
+            Err(HmmerError::InvalidArgument(_))
+        ));
+
+        // The edits survive writing the model out and reading it back
+        let text = hmm.to_ascii_string(HmmFileFormat::V3f).unwrap();
+        assert!(text.contains("GA    25.00 20.50\n"));
+        let reread = &Hmm::read_hmms_from_str(&text).unwrap()[0];
+        assert_eq!(reread.name(), "rplB");
+        assert_eq!(reread.acc(), "PF00181.1");
+        assert_eq!(reread.desc(), "Ribosomal protein L2");
+        assert_eq!(
+            reread.gathering_cutoffs(),
+            Some(ScoreCutoffs {
+                sequence: 25.0,
+                domain: 20.5
+            })
+        );
+        assert_eq!(reread.trusted_cutoffs(), None);
+        for (a, b) in reread.composition().unwrap().iter().zip(&composition) {
+            assert!((a - b).abs() < 1e-3);
+        }
+
+        hmm.set_accession(None).unwrap();
+        hmm.set_description(None).unwrap();
+        assert_eq!(hmm.acc(), "");
+        assert_eq!(hmm.desc(), "");
+    }
+
+    #[test]
+    fn test_hmm_nodes() {
+        let hmm = rplb_hmm();
+
+        let symbols: String = hmm.emission_symbols().into_iter().collect();
+        assert_eq!(symbols, "ACDEFGHIKLMNPQRSTVWY");
+        assert_eq!(hmm.nodes().count(), 139);
+        assert!(hmm.node(139).is_some());
+        assert!(hmm.node(140).is_none());
+
+        // File values are -ln(p): node 1 match A is 2.51805, m->m 0.04829
+        let node = hmm.nodes().next().unwrap();
+        assert_eq!(node.index(), 1);
+        assert_eq!(node.match_emissions().len(), 20);
+        assert!((node.match_emission('A').unwrap() - (-2.51805f32).exp()).abs() < 1e-5);
+        assert_eq!(node.match_emission('a'), node.match_emission('A'));
+        assert_eq!(node.match_emission('-'), None);
+        assert!((node.transition(Transition::MatchToMatch) - (-0.04829f32).exp()).abs() < 1e-5);
+        assert!((node.match_emissions().iter().sum::<f32>() - 1.0).abs() < 1e-4);
+        assert!((node.insert_emissions().iter().sum::<f32>() - 1.0).abs() < 1e-4);
+
+        let background = hmm.background_frequencies();
+        let scores = node.match_scores();
+        assert!((scores[0] - (node.match_emissions()[0] / background[0]).log2()).abs() < 1e-6);
+        assert_eq!(
+            node.transition_scores()[Transition::MatchToMatch.index()],
+            node.transition(Transition::MatchToMatch).log2()
+        );
+        assert_eq!(Transition::InsertToInsert.label(), "i->i");
+    }
+
+    #[test]
+    fn test_write_ascii_round_trip() {
+        let hmm = rplb_hmm();
+        let text = hmm.to_ascii_string(HmmFileFormat::default()).unwrap();
+        assert!(text.starts_with("HMMER3/f"), "{text}");
+        assert!(text.ends_with("//\n"));
+
+        let reread = Hmm::read_hmms_from_str(&text).unwrap();
+        assert_eq!(reread.len(), 1);
+        assert_eq!(reread[0].name(), "S2.1.ribosomal_protein_L2_rplB");
+        assert_eq!(reread[0].length(), 139);
+
+        let legacy = hmm.to_ascii_string(HmmFileFormat::V3a).unwrap();
+        assert!(legacy.starts_with("HMMER3/a"), "{legacy}");
+        let h2 = hmm.to_ascii_string(HmmFileFormat::V2).unwrap();
+        assert!(h2.starts_with("HMMER2.0"), "{h2}");
+    }
+
+    #[test]
+    fn test_write_binary_round_trip() {
+        let hmm = rplb_hmm();
+        let path = std::env::temp_dir().join("hmmer_rs_test_write_binary.h3m");
+        hmm.write_binary_to_path(&path, HmmFileFormat::V3f).unwrap();
+
+        let reread = Hmm::read_hmms_from_path(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(reread.len(), 1);
+        assert_eq!(reread[0].name(), "S2.1.ribosomal_protein_L2_rplB");
+        assert_eq!(reread[0].length(), 139);
+
+        let mut buf = Vec::new();
+        assert!(matches!(
+            hmm.write_binary(&mut buf, HmmFileFormat::V2),
+            Err(HmmerError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_ssi_index_and_fetch() {
+        let path = std::env::temp_dir().join("hmmer_rs_test_fetch.hmm");
+        let mut contents =
+            std::fs::read("tests/data/DNGNGWU00010_mingle_output_good_seqs.hmm").unwrap();
+        contents
+            .extend(std::fs::read("tests/data/DNGNGWU00030_mingle_output_good_seqs.hmm").unwrap());
+        std::fs::write(&path, contents).unwrap();
+        let ssi_path = path.with_extension("hmm.ssi");
+
+        // Without an index, fetching reads through the file
+        let mut reader = HmmReader::from_path(&path).unwrap();
+        assert!(!reader.has_ssi_index());
+        let hmm = reader
+            .fetch("DNGNGWU00030_mingle_output_good_seqs_MJ6Mc8.aln")
+            .unwrap();
+        assert_eq!(hmm.length(), 245);
+
+        assert_eq!(HmmReader::create_ssi_index(&path).unwrap(), 2);
+        // An existing index is replaced
+        assert_eq!(HmmReader::create_ssi_index(&path).unwrap(), 2);
+        let mut reader = HmmReader::from_path(&path).unwrap();
+        assert!(reader.has_ssi_index());
+        let hmm = reader
+            .fetch("DNGNGWU00030_mingle_output_good_seqs_MJ6Mc8.aln")
+            .unwrap();
+        assert_eq!(hmm.length(), 245);
+        // Fetching can go backwards through the file
+        let hmm = reader.fetch("S2.1.ribosomal_protein_L2_rplB").unwrap();
+        assert_eq!(hmm.length(), 139);
+        assert!(matches!(
+            reader.fetch("not_a_model"),
+            Err(HmmerError::NotFound(_))
+        ));
+
+        let hmm = Hmm::fetch_from_path(&path, "S2.1.ribosomal_protein_L2_rplB").unwrap();
+        assert_eq!(hmm.name(), "S2.1.ribosomal_protein_L2_rplB");
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&ssi_path).unwrap();
+    }
+
+    #[test]
+    fn test_read_msas_from_path() {
+        let msas = EaselMsa::read_msas_from_path(
+            std::path::Path::new("tests/data/rplB_fragment.sto"),
+            Alphabet::Protein,
+        )
+        .unwrap();
+        assert_eq!(msas.len(), 1);
+        let msa = &msas[0];
+        assert_eq!(msa.name(), Some("rplB_fragment".to_string()));
+        assert_eq!(msa.num_sequences(), 6);
+        assert_eq!(msa.alignment_length(), 80);
+        assert_eq!(msa.sequence_name(4), "seq5");
+        assert!(msa.aligned_sequence(4).starts_with("----GPNAPI"));
+    }
+
+    #[test]
+    fn test_hmmbuild() {
+        let msas = EaselMsa::read_msas_from_path(
+            std::path::Path::new("tests/data/rplB_fragment.sto"),
+            Alphabet::Protein,
+        )
+        .unwrap();
+        let mut builder = HmmBuilder::new(Alphabet::Protein).unwrap();
+        let hmm = builder.build(&msas[0]).unwrap();
+        assert_eq!(hmm.name(), "rplB_fragment");
+        assert_eq!(hmm.length(), 80);
+
+        // The built HMM is calibrated, so can be searched with directly,
+        // and outlives the builder.
+        drop(builder);
+        let seq = query_seq("seq1");
+        let mut pipeline = HmmerPipeline::new(&hmm).unwrap();
+        let result = pipeline.search_sequences(&[seq]).unwrap();
+        assert_eq!(result.nreported(), 1);
+    }
+
+    #[test]
+    fn test_hmmbuild_options() {
+        let msas = EaselMsa::read_msas_from_path(
+            std::path::Path::new("tests/data/rplB_fragment.sto"),
+            Alphabet::Protein,
+        )
+        .unwrap();
+
+        // Columns 1-4 and 31-33 contain gaps, so are not consensus when
+        // every sequence must have a residue.
+        let mut builder = HmmBuilder::new(Alphabet::Protein)
+            .unwrap()
+            .with_symfrac(1.0)
+            .with_wgt_strategy(WeightStrategy::None)
+            .with_effn_strategy(EffnStrategy::Set(3.0))
+            .with_prior(PriorStrategy::Laplace)
+            .unwrap();
+        let hmm = builder.build(&msas[0]).unwrap();
+        assert_eq!(hmm.length(), 73);
+
+        // --hand requires RF annotation, which this alignment lacks
+        let mut builder = HmmBuilder::new(Alphabet::Protein)
+            .unwrap()
+            .with_arch_strategy(ArchStrategy::Hand);
+        assert!(matches!(
+            builder.build(&msas[0]),
+            Err(HmmerError::Format(_))
+        ));
+    }
+
+    #[test]
+    fn test_hmmbuild_from_hmmalign() {
+        let hmm = rplb_hmm();
+        let seq1 = query_seq("seq1");
+        let mut seq2 = EaselSequence::new(Alphabet::Protein);
+        seq2.replace_sequence(
+            b"MVYSGANAPIEVGNSLPLSDIPAATEIHNIELTNFKGGQLVRSAGSSAKLLAKEGNYVTLRSDSGEMRYVRKECYAAYGQ",
+        )
+        .unwrap();
+        seq2.set_name("seq2").unwrap();
+        let mut msa = HmmerAlign::new(&hmm)
+            .align_sequences(&[seq1, seq2])
+            .unwrap();
+
+        // Alignments made by hmmalign are unnamed, and need a name to build
+        let mut builder = HmmBuilder::new(Alphabet::Protein).unwrap();
+        assert!(matches!(
+            builder.build(&msa),
+            Err(HmmerError::InvalidArgument(_))
+        ));
+        msa.set_name("realigned").unwrap();
+        let hmm = builder.build(&msa).unwrap();
+        assert_eq!(hmm.name(), "realigned");
+        assert!(hmm.length() > 0);
+    }
+
+    #[test]
+    fn test_hmmbuild_from_sequence() {
+        let query = query_seq("query");
+
+        let mut builder = HmmBuilder::new(Alphabet::Protein).unwrap();
+        let hmm = builder.build_from_sequence(&query).unwrap();
+        drop(builder);
+        assert_eq!(hmm.name(), "query");
+        assert_eq!(hmm.length(), 80);
+
+        let mut target = EaselSequence::new(Alphabet::Protein);
+        target
+            .replace_sequence(
+                b"MVYSGANAPIEVGNSLPLSDIPAATEIHNIELTNFKGGQLVRSAGSSAKLLAKEGNYVTLRSDSGEMRYVRKECYAAYGQ",
+            )
+            .unwrap();
+        target.set_name("target").unwrap();
+        let mut pipeline = HmmerPipeline::new(&hmm).unwrap();
+        let result = pipeline.search_sequences(&[target]).unwrap();
+        assert_eq!(result.nreported(), 1);
+    }
+
+    #[test]
+    fn test_hmmbuild_score_system() {
+        let builder = HmmBuilder::new(Alphabet::Protein)
+            .unwrap()
+            .with_score_system("PAM30", 0.02, 0.4);
+        assert!(builder.is_ok());
+
+        let builder = HmmBuilder::new(Alphabet::Protein)
+            .unwrap()
+            .with_score_system("NOT_A_MATRIX", 0.02, 0.4);
+        assert!(matches!(builder, Err(HmmerError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_jackhmmer() {
+        let msas = EaselMsa::read_msas_from_path(
+            std::path::Path::new("tests/data/rplB_fragment.sto"),
+            Alphabet::Protein,
+        )
+        .unwrap();
+        let msa = &msas[0];
+        let mut targets: Vec<EaselSequence> = (0..msa.num_sequences())
+            .map(|i| {
+                let mut seq = EaselSequence::new(Alphabet::Protein);
+                let residues: Vec<u8> = msa
+                    .aligned_sequence(i)
+                    .bytes()
+                    .filter(|c| *c != b'-')
+                    .collect();
+                seq.replace_sequence(&residues).unwrap();
+                seq.set_name(&msa.sequence_name(i)).unwrap();
+                seq
+            })
+            .collect();
+        let mut unrelated = EaselSequence::new(Alphabet::Protein);
+        unrelated
+            .replace_sequence(b"MSKGEELFTGVVPILVELDGDVNGHKFSVSGEGEGDATYGKLTLKFICTTGKLPVPWPTLVTTF")
+            .unwrap();
+        unrelated.set_name("unrelated").unwrap();
+        targets.push(unrelated);
+
+        let query = query_seq("query");
+
+        let mut jackhmmer = Jackhmmer::new(Alphabet::Protein)
+            .unwrap()
+            .with_max_iterations(3);
+        let result = jackhmmer.search_from_sequence(&query, &targets).unwrap();
+
+        assert!(result.converged());
+        let iterations = result.iterations();
+        assert!(iterations.len() >= 2 && iterations.len() <= 3);
+        assert_eq!(iterations[0].iteration(), 1);
+        assert!(iterations[0].new_targets() > 0);
+        assert_eq!(iterations.last().unwrap().new_targets(), 0);
+        for hit in iterations.last().unwrap().result().hits() {
+            assert_ne!(hit.name(), "unrelated");
+        }
+        assert_eq!(iterations[0].hmm().name(), "query");
+        assert!(result.final_hmm().length() > 0);
+
+        // The query plus the included hits
+        let final_msa = result.msa().unwrap();
+        assert_eq!(
+            final_msa.name(),
+            Some(format!("query-i{}", iterations.len()))
+        );
+        assert!(final_msa.num_sequences() > 1);
+
+        // Searching from a model copies it, leaving the caller's model usable
+        let hmm = rplb_hmm();
+        let result = jackhmmer.search_from_hmm(&hmm, &targets).unwrap();
+        drop(result);
+        assert_eq!(hmm.name(), "S2.1.ribosomal_protein_L2_rplB");
+    }
+
+    #[test]
+    fn test_hmmscan() {
+        let mut hmms = vec![rplb_hmm()];
+        hmms.extend(
+            Hmm::read_hmms_from_path(std::path::Path::new(
+                "tests/data/DNGNGWU00030_mingle_output_good_seqs.hmm",
+            ))
+            .unwrap(),
+        );
+
+        let mut pipeline = HmmscanPipeline::new(&hmms).unwrap();
+        assert_eq!(pipeline.nmodels(), 2);
+        // The pipeline keeps its own copy of what it needs from the models
+        drop(hmms);
+
+        let query = query_seq("query");
+
+        // Scanning twice gives the same answer, as Z is reset for each query
+        for _ in 0..2 {
+            let result = pipeline.scan_sequence(&query).unwrap();
+            assert_eq!(result.nreported(), 1);
+            for hit in result.hits() {
+                assert_eq!(hit.name(), "S2.1.ribosomal_protein_L2_rplB");
+                assert_eq!(hit.acc(), "");
+                // Z is the number of models
+                assert_eq!(unsafe { (*result.c_pli).Z }, 2.0);
+                for domain in hit {
+                    assert!(domain.ali_from() >= domain.env_from());
+                    assert!(domain.ali_to() <= domain.env_to());
+                    assert!(domain.ali_from() < domain.ali_to());
+                    assert!(domain.env_to() <= 80);
+                    assert!(domain.hmm_from() >= 1);
+                    assert!(domain.hmm_to() <= 139);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_hmmscan_alphabet_mismatch() {
+        let mut pipeline = HmmscanPipeline::new(&[rplb_hmm()]).unwrap();
+
+        let mut query = EaselSequence::new(Alphabet::DNA);
+        query.replace_sequence(b"ACGTACGTACGT").unwrap();
+        assert!(matches!(
+            pipeline.scan_sequence(&query),
+            Err(HmmerError::Incompatible(_))
+        ));
+        assert!(matches!(
+            HmmscanPipeline::new(&[]),
+            Err(HmmerError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_hmmpress_and_scan() {
+        let mut hmms = vec![rplb_hmm()];
+        hmms.extend(
+            Hmm::read_hmms_from_path(std::path::Path::new(
+                "tests/data/DNGNGWU00030_mingle_output_good_seqs.hmm",
+            ))
+            .unwrap(),
+        );
+        let path = std::env::temp_dir().join("hmmer_rs_test_hmmpress.hmm");
+        Hmm::press_hmms_to_path(&hmms, &path).unwrap();
+
+        let query = query_seq("query");
+
+        let mut pressed = HmmscanPipeline::from_pressed_path(&path).unwrap();
+        assert_eq!(pressed.nmodels(), 2);
+        let pressed_result = pressed.scan_sequence(&query).unwrap();
+        let mut in_memory = HmmscanPipeline::new(&hmms).unwrap();
+        let in_memory_result = in_memory.scan_sequence(&query).unwrap();
+
+        for suffix in ["h3m", "h3i", "h3f", "h3p"] {
+            std::fs::remove_file(path.with_extension(format!("hmm.{suffix}"))).unwrap();
+        }
+
+        assert_eq!(pressed_result.nreported(), 1);
+        let pressed_hit = pressed_result.hits().next().unwrap();
+        let in_memory_hit = in_memory_result.hits().next().unwrap();
+        assert_eq!(pressed_hit.name(), "S2.1.ribosomal_protein_L2_rplB");
+        assert!((pressed_hit.score() - in_memory_hit.score()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_hmmscan_from_unpressed_path() {
+        let result = HmmscanPipeline::from_pressed_path(std::path::Path::new(
+            "tests/data/DNGNGWU00010_mingle_output_good_seqs.hmm",
+        ));
+        assert!(matches!(result, Err(HmmerError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_replace_sequence_invalid_character() {
+        let mut seq = EaselSequence::new(Alphabet::Protein);
+        let result = seq.replace_sequence(b"MVYS1GPN");
+        assert!(matches!(result, Err(HmmerError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_hmmsearch_on_missing_file() {
+        let hmm = rplb_hmm();
+
+        let mut pipeline = HmmerPipeline::new(&hmm).unwrap();
+        let result =
+            pipeline.run_hmm_on_file(&hmm, std::path::Path::new("tests/data/does_not_exist.faa"));
+        assert!(matches!(result, Err(HmmerError::NotFound(_))));
+    }
 }